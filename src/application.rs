@@ -25,6 +25,7 @@ use gtk::{gio, glib};
 
 use crate::MobilisationWindow;
 use crate::config::VERSION;
+use crate::preferences_dialog::MobilisationPreferencesDialog;
 
 mod imp {
     use super::*;
@@ -45,6 +46,8 @@ mod imp {
             let obj = self.obj();
             obj.setup_gactions();
             obj.set_accels_for_action("app.quit", &["<primary>q"]);
+            obj.set_accels_for_action("app.preferences", &["<primary>comma"]);
+            obj.set_accels_for_action("win.search", &["<primary>f"]);
         }
     }
 
@@ -92,7 +95,15 @@ impl MobilisationApplication {
         let about_action = gio::ActionEntry::builder("about")
             .activate(move |app: &Self, _, _| app.show_about())
             .build();
-        self.add_action_entries([quit_action, about_action]);
+        let preferences_action = gio::ActionEntry::builder("preferences")
+            .activate(move |app: &Self, _, _| app.show_preferences())
+            .build();
+        self.add_action_entries([quit_action, about_action, preferences_action]);
+    }
+
+    fn show_preferences(&self) {
+        // Without a window, e.g. when activated over D-Bus, the dialog stands alone
+        MobilisationPreferencesDialog::new().present(self.active_window().as_ref());
     }
 
     fn show_about(&self) {
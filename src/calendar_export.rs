@@ -0,0 +1,73 @@
+use adw::prelude::*;
+use gettextrs::gettext;
+use gtk::{gio, glib};
+
+use crate::core::icalendar::{CalendarEvent, to_icalendar};
+use crate::presentation::dates::time_zone_offset;
+use crate::presentation::html::to_plain_text;
+
+/// Asks where to save `events` as an `.ics` file, named after `title` by
+/// default, and tells when the file could not be written.
+pub fn export_calendar(parent: &impl IsA<gtk::Widget>, title: &str, events: Vec<CalendarEvent>) {
+    let window = parent.root().and_downcast::<gtk::Window>();
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some(&gettext("Calendars")));
+    filter.add_mime_type("text/calendar");
+    filter.add_suffix("ics");
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+    filters.append(&filter);
+    let dialog = gtk::FileDialog::builder()
+        .title(gettext("Export to Calendar"))
+        .initial_name(format!("{}.ics", file_name(title)))
+        .filters(&filters)
+        .default_filter(&filter)
+        .build();
+    glib::spawn_future_local(async move {
+        let file = match dialog.save_future(window.as_ref()).await {
+            Ok(file) => file,
+            Err(err) => {
+                if !err.matches(gtk::DialogError::Dismissed) {
+                    glib::g_log!(glib::LogLevel::Warning, "Error picking a file : {}", err);
+                }
+                return;
+            }
+        };
+        // Descriptions are written in HTML, calendars expect text
+        let events = events
+            .into_iter()
+            .map(|event| CalendarEvent {
+                description: event.description.as_deref().map(to_plain_text),
+                ..event
+            })
+            .collect::<Vec<CalendarEvent>>();
+        let calendar = to_icalendar(&events, chrono::Utc::now(), time_zone_offset);
+        if let Err((_, err)) = file
+            .replace_contents_future(
+                calendar.into_bytes(),
+                None,
+                false,
+                gio::FileCreateFlags::REPLACE_DESTINATION,
+            )
+            .await
+        {
+            glib::g_log!(glib::LogLevel::Warning, "Error exporting events : {}", err);
+            show_export_error(window.as_ref(), err.message());
+        }
+    });
+}
+
+/// Tells that the events could not be exported, and why.
+pub fn show_export_error(parent: Option<&impl IsA<gtk::Widget>>, message: &str) {
+    let alert = adw::AlertDialog::new(Some(&gettext("Could Not Export Events")), Some(message));
+    alert.add_response("close", &gettext("_Close"));
+    alert.present(parent);
+}
+
+/// A title without the characters file systems do not allow in names.
+fn file_name(title: &str) -> String {
+    let name = title.replace(['/', '\\'], "-");
+    match name.trim() {
+        "" => gettext("Events"),
+        name => name.to_string(),
+    }
+}
@@ -0,0 +1,118 @@
+use gtk::glib;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// Longest wait before watches are looked at again. Timeouts do not count
+/// the time the computer sleeps, so a watch due in hours could be missed by
+/// as long.
+const MAX_DELAY: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+/// Called when a watch is due, with the current time, and telling when it is
+/// due next, if ever.
+type Callback =
+    Rc<dyn Fn(chrono::DateTime<chrono::Local>) -> Option<chrono::DateTime<chrono::Local>>>;
+
+/// Wakes up what shows times when it has to change, with a single timeout
+/// for all of them, set for the earliest.
+#[derive(Default)]
+struct Clock {
+    watches: RefCell<Vec<Watch>>,
+    timeout: RefCell<Option<glib::SourceId>>,
+    last_id: Cell<u64>,
+}
+
+struct Watch {
+    id: u64,
+    due: chrono::DateTime<chrono::Local>,
+    callback: Callback,
+}
+
+/// Keeps a watch going, until dropped.
+#[derive(Debug)]
+pub struct ClockWatch {
+    id: u64,
+}
+
+thread_local! {
+    /// Widgets live on the main thread, and so does the clock.
+    static CLOCK: Clock = Clock::default();
+}
+
+/// Calls `callback` at `due`, then whenever it says it is due next.
+pub fn watch(
+    due: chrono::DateTime<chrono::Local>,
+    callback: impl Fn(chrono::DateTime<chrono::Local>) -> Option<chrono::DateTime<chrono::Local>>
+    + 'static,
+) -> ClockWatch {
+    CLOCK.with(|clock| {
+        let id = clock.last_id.get() + 1;
+        clock.last_id.set(id);
+        clock.watches.borrow_mut().push(Watch {
+            id,
+            due,
+            callback: Rc::new(callback),
+        });
+        clock.schedule();
+        ClockWatch { id }
+    })
+}
+
+impl Drop for ClockWatch {
+    fn drop(&mut self) {
+        // The clock may already be gone when the thread ends
+        let _ = CLOCK.try_with(|clock| {
+            clock
+                .watches
+                .borrow_mut()
+                .retain(|watch| watch.id != self.id);
+        });
+    }
+}
+
+impl Clock {
+    /// Sets the timeout for the earliest watch, replacing the previous one.
+    fn schedule(&self) {
+        if let Some(timeout) = self.timeout.take() {
+            timeout.remove();
+        }
+        let Some(due) = self.watches.borrow().iter().map(|watch| watch.due).min() else {
+            return;
+        };
+        let delay = (due - chrono::Local::now())
+            .to_std()
+            .unwrap_or_default()
+            .min(MAX_DELAY);
+        let timeout = glib::timeout_add_local_once(delay, || {
+            CLOCK.with(|clock| {
+                // The timeout is over, it must not be removed again
+                clock.timeout.take();
+                clock.tick();
+            })
+        });
+        self.timeout.replace(Some(timeout));
+    }
+
+    fn tick(&self) {
+        let now = chrono::Local::now();
+        // Callbacks may add or drop watches, so none is borrowed meanwhile
+        let due = self
+            .watches
+            .borrow()
+            .iter()
+            .filter(|watch| watch.due <= now)
+            .map(|watch| (watch.id, watch.callback.clone()))
+            .collect::<Vec<(u64, Callback)>>();
+        due.into_iter().for_each(|(id, callback)| {
+            let next = callback(now);
+            let mut watches = self.watches.borrow_mut();
+            match next {
+                Some(next) => watches
+                    .iter_mut()
+                    .filter(|watch| watch.id == id)
+                    .for_each(|watch| watch.due = next),
+                None => watches.retain(|watch| watch.id != id),
+            }
+        });
+        self.schedule();
+    }
+}
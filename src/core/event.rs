@@ -1,20 +1,105 @@
 use chrono::*;
+use std::str::FromStr;
 use url::Url;
 use uuid::Uuid;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Event {
     pub id: Uuid,
     pub title: String,
+    /// As written by the organizer, in HTML
+    pub description: Option<String>,
     pub picture_url: Option<Url>,
     pub begins_on: DateTime,
     pub ends_on: DateTime,
+    pub status: EventStatus,
+    /// Where the event takes place, by its name in the IANA time zone
+    /// database such as "Europe/Paris", when the organizer gave one.
+    pub time_zone: Option<String>,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EventStatus {
+    #[default]
+    Confirmed,
+    Tentative,
+    Cancelled,
+}
+
+impl EventStatus {
+    pub const ALL: [EventStatus; 3] = [
+        EventStatus::Confirmed,
+        EventStatus::Tentative,
+        EventStatus::Cancelled,
+    ];
+
+    /// Name of the status in the Mobilizon API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventStatus::Confirmed => "CONFIRMED",
+            EventStatus::Tentative => "TENTATIVE",
+            EventStatus::Cancelled => "CANCELLED",
+        }
+    }
+}
+
+impl FromStr for EventStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EventStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or(s.to_string())
+    }
+}
+
+/// Group of the timeline an event is shown in, in the order groups come.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TimelineSection {
+    HappeningNow,
+    Today,
+    ThisWeek,
+    ThisMonth,
+    #[default]
+    Later,
+}
+
+/// How much of a date is worth showing, the further the date the more.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatePrecision {
+    Time,
+    Weekday,
+    WeekdayAndDay,
+    MonthAndDay,
+    MonthDayAndYear,
+}
+
+/// Events beginning within this many minutes are said to be starting soon.
+const STARTING_SOON_MINUTES: i64 = 60;
+
+/// Where an event stands in time, left for the interface to write in the
+/// language of the user. Dates keep the offset they are to be shown with.
 #[derive(Debug, PartialEq)]
 pub enum HumanReadableDateTime {
-    Now,
-    Later(String),
+    /// The event is over
+    Ended,
+    /// The event is happening and ends after `remaining`
+    Ongoing {
+        remaining: chrono::TimeDelta,
+    },
+    /// The event lasts several days and is on its `day`th day out of `days`
+    OngoingDay {
+        day: i64,
+        days: i64,
+    },
+    /// The event begins within the hour, in `minutes` rounded up
+    StartingIn {
+        minutes: i64,
+    },
+    /// The event begins tomorrow, at the time given
+    Tomorrow(chrono::DateTime<FixedOffset>),
+    Later(DatePrecision, chrono::DateTime<FixedOffset>),
 }
 
 impl Event {
@@ -38,46 +123,154 @@ impl Event {
             None => Self::get_now(),
             Some(f) => f(),
         };
-        let underlying_local = self.begins_on.underlying.with_timezone(&chrono::Local);
+        self.human_readable_begining_at(&now)
+    }
+
+    /// Where the event stands at `now`, days being counted in the time zone
+    /// of `now`.
+    pub fn human_readable_begining_at<Tz: TimeZone>(
+        &self,
+        now: &chrono::DateTime<Tz>,
+    ) -> HumanReadableDateTime {
+        let underlying_local = self
+            .begins_on
+            .underlying
+            .with_timezone(&now.timezone())
+            .fixed_offset();
+        let until_beginning = self.begins_on.underlying - now.to_utc();
         let breakpoints = Breakpoints::new(now);
         match breakpoints {
-            _ if self.begins_on.underlying < now && now < self.ends_on.underlying => {
-                HumanReadableDateTime::Now
+            _ if *now >= self.ends_on.underlying => HumanReadableDateTime::Ended,
+            _ if self.is_happening(now) && self.is_long() => self.ongoing_day(now),
+            _ if self.is_happening(now) => HumanReadableDateTime::Ongoing {
+                remaining: self.ends_on.underlying - now.to_utc(),
+            },
+            _ if until_beginning <= TimeDelta::minutes(STARTING_SOON_MINUTES) => {
+                HumanReadableDateTime::StartingIn {
+                    minutes: minutes_rounded_up(until_beginning),
+                }
             }
             Some(breakpoints) if underlying_local < breakpoints.midnight => {
-                HumanReadableDateTime::Later(underlying_local.format("%H:%M").to_string())
+                HumanReadableDateTime::Later(DatePrecision::Time, underlying_local)
+            }
+            Some(breakpoints) if underlying_local < breakpoints.day_after_tomorrow => {
+                HumanReadableDateTime::Tomorrow(underlying_local)
             }
             Some(breakpoints)
                 if underlying_local >= breakpoints.midnight
                     && underlying_local < breakpoints.next_week =>
             {
-                HumanReadableDateTime::Later(underlying_local.format("%A").to_string())
+                HumanReadableDateTime::Later(DatePrecision::Weekday, underlying_local)
             }
             Some(breakpoints)
                 if underlying_local >= breakpoints.next_week
                     && underlying_local < breakpoints.next_month =>
             {
-                HumanReadableDateTime::Later(underlying_local.format("%A %e").to_string())
+                HumanReadableDateTime::Later(DatePrecision::WeekdayAndDay, underlying_local)
             }
             Some(breakpoints)
                 if underlying_local >= breakpoints.next_month
                     && underlying_local < breakpoints.next_year =>
             {
-                HumanReadableDateTime::Later(underlying_local.format("%B %e").to_string())
+                HumanReadableDateTime::Later(DatePrecision::MonthAndDay, underlying_local)
+            }
+            _ => HumanReadableDateTime::Later(DatePrecision::MonthDayAndYear, underlying_local),
+        }
+    }
+
+    /// First time after `now` when `human_readable_begining_at` may tell
+    /// something else: every minute while minutes are counted, when the event
+    /// is about to begin, begins or ends, or at midnight when every
+    /// breakpoint moves. Ended events never change again.
+    pub fn next_human_readable_change<Tz: TimeZone>(
+        &self,
+        now: chrono::DateTime<Tz>,
+    ) -> Option<chrono::DateTime<Tz>> {
+        let zone = now.timezone();
+        let begins_on = self.begins_on.underlying.with_timezone(&zone);
+        let ends_on = self.ends_on.underlying.with_timezone(&zone);
+        // When the minutes rounded up go down by one
+        let next_minute = match self.human_readable_begining_at(&now) {
+            HumanReadableDateTime::Ended => return None,
+            HumanReadableDateTime::StartingIn { minutes } => {
+                Some(begins_on.clone() - TimeDelta::minutes(minutes - 1))
             }
-            Some(breakpoints) if underlying_local >= breakpoints.next_year => {
-                HumanReadableDateTime::Later(underlying_local.format("%B %e %G").to_string())
+            HumanReadableDateTime::Ongoing { remaining } => {
+                let minutes = minutes_rounded_up(remaining);
+                Some(ends_on.clone() - TimeDelta::minutes(minutes - 1))
             }
-            _ => HumanReadableDateTime::Later(underlying_local.to_string()),
+            _ => None,
+        };
+        [
+            Some(begins_on.clone() - TimeDelta::minutes(STARTING_SOON_MINUTES)),
+            Some(begins_on),
+            Some(ends_on),
+            Breakpoints::new(&now).map(|breakpoints| breakpoints.midnight),
+            next_minute,
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|time| *time > now)
+        .min()
+    }
+
+    /// Which day of a long event `now` is, counted in calendar days.
+    fn ongoing_day<Tz: TimeZone>(&self, now: &chrono::DateTime<Tz>) -> HumanReadableDateTime {
+        let zone = now.timezone();
+        let first_day = self.begins_on.underlying.with_timezone(&zone).date_naive();
+        // An event ending at midnight does not take place on the next day
+        let last_day = (self.ends_on.underlying - TimeDelta::seconds(1))
+            .with_timezone(&zone)
+            .date_naive();
+        let days = (last_day - first_day).num_days() + 1;
+        HumanReadableDateTime::OngoingDay {
+            day: ((now.date_naive() - first_day).num_days() + 1).clamp(1, days),
+            days,
+        }
+    }
+
+    /// Group of the timeline the event falls in at `now`, cut at the same
+    /// breakpoints as `human_readable_begining_at`.
+    pub fn timeline_section<Tz: TimeZone>(&self, now: chrono::DateTime<Tz>) -> TimelineSection {
+        let begins_on = self.begins_on.underlying;
+        match Breakpoints::new(&now) {
+            _ if self.is_happening(&now) => TimelineSection::HappeningNow,
+            Some(breakpoints) if begins_on < breakpoints.midnight => TimelineSection::Today,
+            Some(breakpoints) if begins_on < breakpoints.next_week => TimelineSection::ThisWeek,
+            Some(breakpoints) if begins_on < breakpoints.next_month => TimelineSection::ThisMonth,
+            _ => TimelineSection::Later,
         }
     }
 
+    /// First time after `now` when the event may move to another group of
+    /// the timeline: when it begins, when it ends, or at midnight when every
+    /// breakpoint moves.
+    pub fn next_section_change<Tz: TimeZone>(
+        &self,
+        now: chrono::DateTime<Tz>,
+    ) -> Option<chrono::DateTime<Tz>> {
+        let zone = now.timezone();
+        [
+            Some(self.begins_on.underlying.with_timezone(&zone)),
+            Some(self.ends_on.underlying.with_timezone(&zone)),
+            Breakpoints::new(&now).map(|breakpoints| breakpoints.midnight),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|time| *time > now)
+        .min()
+    }
+
+    fn is_happening<Tz: TimeZone>(&self, now: &chrono::DateTime<Tz>) -> bool {
+        self.begins_on.underlying < *now && *now < self.ends_on.underlying
+    }
+
     pub fn get_now() -> chrono::DateTime<chrono::Local> {
         chrono::Local::now()
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct DateTime {
     underlying: chrono::DateTime<chrono::Utc>,
 }
@@ -90,43 +283,64 @@ impl DateTime {
     pub fn to_rfc3339(&self) -> String {
         self.underlying.to_rfc3339()
     }
+
+    pub fn to_utc(&self) -> chrono::DateTime<chrono::Utc> {
+        self.underlying
+    }
+}
+
+/// Whole minutes in `delta`, a started minute counting as a whole one.
+pub fn minutes_rounded_up(delta: TimeDelta) -> i64 {
+    let minutes = delta.num_minutes();
+    if delta > TimeDelta::minutes(minutes) {
+        minutes + 1
+    } else {
+        minutes
+    }
 }
 
+/// Where days, weeks, months and years after the one of `now` begin, in the
+/// time zone of `now`.
 #[derive(Debug)]
-struct Breakpoints {
-    midnight: chrono::DateTime<chrono::Local>,
-    next_week: chrono::DateTime<chrono::Local>,
-    next_month: chrono::DateTime<chrono::Local>,
-    next_year: chrono::DateTime<chrono::Local>,
+struct Breakpoints<Tz: TimeZone> {
+    midnight: chrono::DateTime<Tz>,
+    day_after_tomorrow: chrono::DateTime<Tz>,
+    next_week: chrono::DateTime<Tz>,
+    next_month: chrono::DateTime<Tz>,
+    next_year: chrono::DateTime<Tz>,
 }
 
-impl Breakpoints {
-    pub fn new(now: chrono::DateTime<Local>) -> Option<Self> {
-        let base = Self::mapped_local_time_to_opt(chrono::Local.with_ymd_and_hms(
-            now.year(),
-            now.month(),
-            now.day(),
-            0,
-            0,
-            0,
-        ));
-        let midnight_breakpoint = base.and_then(|t| t.checked_add_days(Days::new(1)))?;
-        let next_week_breakpoint = base.and_then(|t| t.checked_add_days(Days::new(7)))?;
-        let next_month_breakpoint = base.and_then(|t| t.checked_add_months(Months::new(1)))?;
-        let next_year_breakpoint = base.and_then(|t| t.with_year(t.year() + 1))?;
+impl<Tz: TimeZone> Breakpoints<Tz> {
+    /// Only fails at the end of times, past the dates chrono can represent.
+    pub fn new(now: &chrono::DateTime<Tz>) -> Option<Self> {
+        let zone = now.timezone();
+        let today = now.date_naive();
+        // Days are counted on the calendar, as they may last 23 or 25 hours
+        let start = |date: Option<NaiveDate>| date.and_then(|date| start_of_day(&zone, date));
         Some(Self {
-            midnight: midnight_breakpoint,
-            next_week: next_week_breakpoint,
-            next_month: next_month_breakpoint,
-            next_year: next_year_breakpoint,
+            midnight: start(today.checked_add_days(Days::new(1)))?,
+            day_after_tomorrow: start(today.checked_add_days(Days::new(2)))?,
+            next_week: start(today.checked_add_days(Days::new(7)))?,
+            next_month: start(today.checked_add_months(Months::new(1)))?,
+            next_year: start(today.checked_add_months(Months::new(12)))?,
         })
     }
+}
 
-    fn mapped_local_time_to_opt<T>(a: chrono::MappedLocalTime<T>) -> Option<T> {
-        match a {
-            chrono::offset::MappedLocalTime::Single(t) => Some(t),
-            chrono::offset::MappedLocalTime::Ambiguous(a, _) => Some(a),
-            chrono::offset::MappedLocalTime::None => None,
+/// First instant of `date` in `zone`.
+fn start_of_day<Tz: TimeZone>(zone: &Tz, date: NaiveDate) -> Option<chrono::DateTime<Tz>> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    match zone.from_local_datetime(&midnight) {
+        MappedLocalTime::Single(start) => Some(start),
+        // Clocks went back around midnight, which happened twice
+        MappedLocalTime::Ambiguous(first, _) => Some(first),
+        // Clocks went forward at midnight, the day begins when they did: at
+        // midnight by the offset of the day before
+        MappedLocalTime::None => {
+            let offset_before = zone
+                .offset_from_utc_datetime(&(midnight - TimeDelta::days(1)))
+                .fix();
+            Some(zone.from_utc_datetime(&midnight.checked_sub_offset(offset_before)?))
         }
     }
 }
@@ -139,6 +353,7 @@ mod tests {
             picture_url: None,
             begins_on: create_date(begining),
             ends_on: create_date(end),
+            ..Default::default()
         }
     }
 
@@ -162,19 +377,25 @@ mod tests {
         let event = create_event("2001-01-30T00:00:00+01:00", "2001-01-30T02:00:00+01:00");
         assert_eq!(
             event.compute_human_readable_begining(fake_now),
-            super::HumanReadableDateTime::Later("00:00".to_string())
+            super::HumanReadableDateTime::StartingIn { minutes: 0 }
         );
 
         let event = create_event("2001-01-30T03:00:00+01:00", "2001-01-30T05:00:00+01:00");
         assert_eq!(
             event.compute_human_readable_begining(fake_now),
-            super::HumanReadableDateTime::Later("03:00".to_string())
+            super::HumanReadableDateTime::Later(
+                super::DatePrecision::Time,
+                create_fake_now("2001-01-30T03:00:00+01:00")().fixed_offset()
+            )
         );
 
         let event = create_event("2001-01-30T23:59:00+01:00", "2001-01-31T00:00:00+01:00");
         assert_eq!(
             event.compute_human_readable_begining(fake_now),
-            super::HumanReadableDateTime::Later("23:59".to_string())
+            super::HumanReadableDateTime::Later(
+                super::DatePrecision::Time,
+                create_fake_now("2001-01-30T23:59:00+01:00")().fixed_offset()
+            )
         );
     }
 
@@ -184,13 +405,18 @@ mod tests {
         let event = create_event("2001-01-31T00:00:00+01:00", "2001-01-31T02:00:00+01:00");
         assert_eq!(
             event.compute_human_readable_begining(fake_now),
-            super::HumanReadableDateTime::Later("Wednesday".to_string())
+            super::HumanReadableDateTime::Tomorrow(
+                create_fake_now("2001-01-31T00:00:00+01:00")().fixed_offset()
+            )
         );
 
         let event = create_event("2001-02-05T23:59:00+01:00", "2001-02-06T23:59:00+01:00");
         assert_eq!(
             event.compute_human_readable_begining(fake_now),
-            super::HumanReadableDateTime::Later("Monday".to_string())
+            super::HumanReadableDateTime::Later(
+                super::DatePrecision::Weekday,
+                create_fake_now("2001-02-05T23:59:00+01:00")().fixed_offset()
+            )
         );
     }
 
@@ -200,7 +426,10 @@ mod tests {
         let event = create_event("2001-01-20T00:00:00+01:00", "2001-01-31T02:00:00+01:00");
         assert_eq!(
             event.compute_human_readable_begining(fake_now),
-            super::HumanReadableDateTime::Later("Saturday 20".to_string())
+            super::HumanReadableDateTime::Later(
+                super::DatePrecision::WeekdayAndDay,
+                create_fake_now("2001-01-20T00:00:00+01:00")().fixed_offset()
+            )
         );
     }
 
@@ -210,7 +439,23 @@ mod tests {
         let event = create_event("2001-02-20T00:00:00+01:00", "2001-01-31T02:00:00+01:00");
         assert_eq!(
             event.compute_human_readable_begining(fake_now),
-            super::HumanReadableDateTime::Later("February 20".to_string())
+            super::HumanReadableDateTime::Later(
+                super::DatePrecision::MonthAndDay,
+                create_fake_now("2001-02-20T00:00:00+01:00")().fixed_offset()
+            )
+        );
+    }
+
+    #[test]
+    fn human_readable_produces_year_after_a_year() {
+        let fake_now = Some(&create_fake_now("2001-01-01T00:00:00+01:00"));
+        let event = create_event("2002-01-01T00:00:00+01:00", "2002-01-01T02:00:00+01:00");
+        assert_eq!(
+            event.compute_human_readable_begining(fake_now),
+            super::HumanReadableDateTime::Later(
+                super::DatePrecision::MonthDayAndYear,
+                create_fake_now("2002-01-01T00:00:00+01:00")().fixed_offset()
+            )
         );
     }
 
@@ -220,8 +465,177 @@ mod tests {
         let event = create_event("2001-01-01T00:00:00+01:00", "2001-01-01T15:00:00+01:00");
         assert_eq!(
             event.compute_human_readable_begining(fake_now),
-            super::HumanReadableDateTime::Now
+            super::HumanReadableDateTime::Ongoing {
+                remaining: chrono::TimeDelta::hours(3)
+            }
+        );
+    }
+
+    #[test]
+    fn human_readable_ended() {
+        let fake_now = Some(&create_fake_now("2001-01-01T12:00:00+01:00"));
+        let event = create_event("2001-01-01T08:00:00+01:00", "2001-01-01T10:00:00+01:00");
+        assert_eq!(
+            event.compute_human_readable_begining(fake_now),
+            super::HumanReadableDateTime::Ended
+        );
+        let event = create_event("2000-12-01T08:00:00+01:00", "2001-01-01T12:00:00+01:00");
+        assert_eq!(
+            event.compute_human_readable_begining(fake_now),
+            super::HumanReadableDateTime::Ended
+        );
+    }
+
+    #[test]
+    fn human_readable_starting_soon() {
+        let fake_now = Some(&create_fake_now("2001-01-01T12:00:00+01:00"));
+        let event = create_event("2001-01-01T12:20:00+01:00", "2001-01-01T14:00:00+01:00");
+        assert_eq!(
+            event.compute_human_readable_begining(fake_now),
+            super::HumanReadableDateTime::StartingIn { minutes: 20 }
+        );
+        let event = create_event("2001-01-01T12:00:30+01:00", "2001-01-01T14:00:00+01:00");
+        assert_eq!(
+            event.compute_human_readable_begining(fake_now),
+            super::HumanReadableDateTime::StartingIn { minutes: 1 }
         );
+        let event = create_event("2001-01-01T12:00:00+01:00", "2001-01-01T14:00:00+01:00");
+        assert_eq!(
+            event.compute_human_readable_begining(fake_now),
+            super::HumanReadableDateTime::StartingIn { minutes: 0 }
+        );
+        let event = create_event("2001-01-01T13:01:00+01:00", "2001-01-01T14:00:00+01:00");
+        assert_eq!(
+            event.compute_human_readable_begining(fake_now),
+            super::HumanReadableDateTime::Later(
+                super::DatePrecision::Time,
+                create_fake_now("2001-01-01T13:01:00+01:00")().fixed_offset()
+            )
+        );
+    }
+
+    #[test]
+    fn human_readable_tomorrow() {
+        let fake_now = Some(&create_fake_now("2001-01-30T22:00:00+01:00"));
+        let event = create_event("2001-01-31T00:00:00+01:00", "2001-01-31T02:00:00+01:00");
+        assert_eq!(
+            event.compute_human_readable_begining(fake_now),
+            super::HumanReadableDateTime::Tomorrow(
+                create_fake_now("2001-01-31T00:00:00+01:00")().fixed_offset()
+            )
+        );
+        let event = create_event("2001-01-31T23:59:00+01:00", "2001-02-01T02:00:00+01:00");
+        assert_eq!(
+            event.compute_human_readable_begining(fake_now),
+            super::HumanReadableDateTime::Tomorrow(
+                create_fake_now("2001-01-31T23:59:00+01:00")().fixed_offset()
+            )
+        );
+    }
+
+    #[test]
+    fn human_readable_day_of_long_events() {
+        let event = create_event("2001-01-01T09:00:00+01:00", "2001-01-03T18:00:00+01:00");
+        let days = [
+            ("2001-01-01T10:00:00+01:00", 1),
+            ("2001-01-02T12:00:00+01:00", 2),
+            ("2001-01-03T17:00:00+01:00", 3),
+        ];
+        days.into_iter().for_each(|(now, day)| {
+            assert_eq!(
+                event.compute_human_readable_begining(Some(&create_fake_now(now))),
+                super::HumanReadableDateTime::OngoingDay { day, days: 3 }
+            );
+        });
+        // Ending at midnight, the event does not take place on the next day
+        let event = create_event("2001-01-01T09:00:00+01:00", "2001-01-03T00:00:00+01:00");
+        assert_eq!(
+            event.compute_human_readable_begining(Some(&create_fake_now(
+                "2001-01-02T23:00:00+01:00"
+            ))),
+            super::HumanReadableDateTime::OngoingDay { day: 2, days: 2 }
+        );
+    }
+
+    #[test]
+    fn timeline_sections_follow_the_breakpoints() {
+        let now = create_fake_now("2001-01-30T10:00:00+01:00")();
+        let sections = [
+            (
+                "2001-01-30T09:00:00+01:00",
+                super::TimelineSection::HappeningNow,
+            ),
+            ("2001-01-30T23:59:00+01:00", super::TimelineSection::Today),
+            (
+                "2001-01-31T00:00:00+01:00",
+                super::TimelineSection::ThisWeek,
+            ),
+            (
+                "2001-02-05T23:59:00+01:00",
+                super::TimelineSection::ThisWeek,
+            ),
+            (
+                "2001-02-06T00:00:00+01:00",
+                super::TimelineSection::ThisMonth,
+            ),
+            ("2001-02-28T00:00:00+01:00", super::TimelineSection::Later),
+        ];
+        sections.into_iter().for_each(|(begining, section)| {
+            let event = create_event(begining, "2001-01-30T11:00:00+01:00");
+            assert_eq!(event.timeline_section(now), section, "{}", begining);
+        });
+    }
+
+    #[test]
+    fn next_section_change_is_the_closest_breakpoint() {
+        let now = create_fake_now("2001-01-30T10:00:00+01:00")();
+        let event = create_event("2001-01-30T18:00:00+01:00", "2001-01-30T20:00:00+01:00");
+        assert_eq!(
+            event.next_section_change(now),
+            Some(create_fake_now("2001-01-30T18:00:00+01:00")())
+        );
+        let event = create_event("2001-01-30T09:00:00+01:00", "2001-01-30T12:00:00+01:00");
+        assert_eq!(
+            event.next_section_change(now),
+            Some(create_fake_now("2001-01-30T12:00:00+01:00")())
+        );
+        let event = create_event("2001-03-01T09:00:00+01:00", "2001-03-01T12:00:00+01:00");
+        assert_eq!(
+            event.next_section_change(now),
+            Some(create_fake_now("2001-01-31T00:00:00+01:00")())
+        );
+    }
+
+    #[test]
+    fn human_readable_changes_every_minute_when_counting_minutes() {
+        let now = create_fake_now("2001-01-01T12:00:00+01:00")();
+        let event = create_event("2001-01-01T12:19:30+01:00", "2001-01-01T14:00:00+01:00");
+        assert_eq!(
+            event.next_human_readable_change(now),
+            Some(create_fake_now("2001-01-01T12:00:30+01:00")())
+        );
+        let event = create_event("2001-01-01T11:00:00+01:00", "2001-01-01T12:10:00+01:00");
+        assert_eq!(
+            event.next_human_readable_change(now),
+            Some(create_fake_now("2001-01-01T12:01:00+01:00")())
+        );
+    }
+
+    #[test]
+    fn human_readable_changes_at_the_next_boundary() {
+        let now = create_fake_now("2001-01-01T12:00:00+01:00")();
+        let event = create_event("2001-01-01T18:00:00+01:00", "2001-01-01T20:00:00+01:00");
+        assert_eq!(
+            event.next_human_readable_change(now),
+            Some(create_fake_now("2001-01-01T17:00:00+01:00")())
+        );
+        let event = create_event("2001-01-05T18:00:00+01:00", "2001-01-05T20:00:00+01:00");
+        assert_eq!(
+            event.next_human_readable_change(now),
+            Some(create_fake_now("2001-01-02T00:00:00+01:00")())
+        );
+        let event = create_event("2001-01-01T08:00:00+01:00", "2001-01-01T10:00:00+01:00");
+        assert_eq!(event.next_human_readable_change(now), None);
     }
 
     #[test]
@@ -231,6 +645,7 @@ mod tests {
             picture_url: None,
             begins_on: create_date("2001-02-20T00:00:00+01:00"),
             ends_on: create_date("2001-02-20T03:00:00+01:00"),
+            ..Default::default()
         };
         assert_eq!(a.is_long(), false);
     }
@@ -242,10 +657,22 @@ mod tests {
             picture_url: None,
             begins_on: create_date("2001-02-20T00:00:00+01:00"),
             ends_on: create_date("2001-02-21T03:00:00+01:00"),
+            ..Default::default()
         };
         assert_eq!(b.is_long(), true);
     }
 
+    #[test]
+    fn event_status_round_trips_through_its_api_name() {
+        super::EventStatus::ALL.into_iter().for_each(|status| {
+            assert_eq!(
+                <super::EventStatus as std::str::FromStr>::from_str(status.as_str()),
+                Ok(status)
+            );
+        });
+        assert!(<super::EventStatus as std::str::FromStr>::from_str("POSTPONED").is_err());
+    }
+
     #[test]
     fn duration_in_hours() {
         let a = super::Event {
@@ -253,7 +680,340 @@ mod tests {
             picture_url: None,
             begins_on: create_date("2001-02-20T00:00:00+01:00"),
             ends_on: create_date("2001-02-20T03:00:00+01:00"),
+            ..Default::default()
         };
         assert_eq!(a.compute_duration_in_hours(), 3);
     }
+
+    /// Days that do not last 24 hours, around daylight saving time.
+    mod transitions {
+        use crate::core::event::{
+            Breakpoints, DatePrecision, DateTime, Event, HumanReadableDateTime, TimelineSection,
+            minutes_rounded_up,
+        };
+        use chrono::{
+            Datelike, Days, FixedOffset, MappedLocalTime, NaiveDate, NaiveDateTime, NaiveTime,
+            Offset, TimeDelta, TimeZone,
+        };
+
+        /// Instants tried by every property, for every zone.
+        const CASES: usize = 2000;
+
+        /// A zone going to summer time on March 26 and back on October 29,
+        /// as chrono itself only knows fixed offsets and the local zone.
+        #[derive(Debug, Clone, Copy)]
+        struct DstZone {
+            /// Offset in winter, in seconds
+            standard: i32,
+            /// How far clocks go forward in summer, in seconds
+            shift: i32,
+            /// Winter time when clocks go forward, in seconds after midnight
+            forward_at: i64,
+            /// Summer time when clocks go back, in seconds after midnight
+            back_at: i64,
+        }
+
+        #[derive(Debug, Clone, Copy)]
+        struct DstOffset {
+            zone: DstZone,
+            offset: FixedOffset,
+        }
+
+        /// Clocks change at night, as in most of Europe.
+        const EUROPE: DstZone = DstZone {
+            standard: 3600,
+            shift: 3600,
+            forward_at: 2 * 3600,
+            back_at: 3 * 3600,
+        };
+
+        /// Clocks change at midnight, which happens twice in October and not
+        /// at all in March, as it used to in Chile or Brazil.
+        const AT_MIDNIGHT: DstZone = DstZone {
+            standard: -4 * 3600,
+            shift: 3600,
+            forward_at: 0,
+            back_at: 3600,
+        };
+
+        /// Clocks change by half an hour, as on Lord Howe Island.
+        const HALF_HOUR: DstZone = DstZone {
+            standard: 37800,
+            shift: 1800,
+            forward_at: 2 * 3600,
+            back_at: 2 * 3600,
+        };
+
+        const ZONES: [DstZone; 3] = [EUROPE, AT_MIDNIGHT, HALF_HOUR];
+
+        impl DstZone {
+            fn is_summer(&self, utc: &NaiveDateTime) -> bool {
+                let transition = |month, seconds| {
+                    NaiveDate::from_ymd_opt(utc.year(), month, if month == 3 { 26 } else { 29 })
+                        .unwrap()
+                        .and_time(NaiveTime::MIN)
+                        + TimeDelta::seconds(seconds)
+                };
+                let forward = transition(3, self.forward_at - self.standard as i64);
+                let back = transition(10, self.back_at - (self.standard + self.shift) as i64);
+                forward <= *utc && *utc < back
+            }
+
+            fn offset(&self, summer: bool) -> DstOffset {
+                let shift = if summer { self.shift } else { 0 };
+                DstOffset {
+                    zone: *self,
+                    offset: FixedOffset::east_opt(self.standard + shift).unwrap(),
+                }
+            }
+        }
+
+        impl Offset for DstOffset {
+            fn fix(&self) -> FixedOffset {
+                self.offset
+            }
+        }
+
+        impl TimeZone for DstZone {
+            type Offset = DstOffset;
+
+            fn from_offset(offset: &DstOffset) -> Self {
+                offset.zone
+            }
+
+            fn offset_from_local_date(&self, local: &NaiveDate) -> MappedLocalTime<DstOffset> {
+                self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+            }
+
+            fn offset_from_local_datetime(
+                &self,
+                local: &NaiveDateTime,
+            ) -> MappedLocalTime<DstOffset> {
+                // Summer time comes first, its offset being the largest
+                let offsets = [true, false]
+                    .into_iter()
+                    .filter(|summer| {
+                        let utc = local.checked_sub_offset(self.offset(*summer).offset);
+                        utc.is_some_and(|utc| self.is_summer(&utc) == *summer)
+                    })
+                    .map(|summer| self.offset(summer))
+                    .collect::<Vec<DstOffset>>();
+                match offsets[..] {
+                    [offset] => MappedLocalTime::Single(offset),
+                    [first, second] => MappedLocalTime::Ambiguous(first, second),
+                    _ => MappedLocalTime::None,
+                }
+            }
+
+            fn offset_from_utc_date(&self, utc: &NaiveDate) -> DstOffset {
+                self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+            }
+
+            fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> DstOffset {
+                self.offset(self.is_summer(utc))
+            }
+        }
+
+        /// Pseudo random numbers from a fixed seed, so that failures replay.
+        struct Random(u64);
+
+        impl Random {
+            fn next(&mut self) -> u64 {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                self.0
+            }
+
+            fn range(&mut self, low: i64, high: i64) -> i64 {
+                low + (self.next() % (high - low) as u64) as i64
+            }
+
+            /// An instant within two days of a change of clocks of `zone`.
+            fn around_transition(&mut self, zone: DstZone) -> chrono::DateTime<DstZone> {
+                let (month, day) = if self.next().is_multiple_of(2) {
+                    (3, 26)
+                } else {
+                    (10, 29)
+                };
+                let day = NaiveDate::from_ymd_opt(self.range(2000, 2040) as i32, month, day)
+                    .unwrap()
+                    .and_time(NaiveTime::MIN);
+                zone.from_utc_datetime(&(day + TimeDelta::seconds(self.range(-172800, 172800))))
+            }
+
+            /// An instant between `from` and `until`, `until` excluded.
+            fn between(
+                &mut self,
+                from: chrono::DateTime<DstZone>,
+                until: chrono::DateTime<DstZone>,
+            ) -> chrono::DateTime<DstZone> {
+                from + TimeDelta::milliseconds(self.range(0, (until - from).num_milliseconds()))
+            }
+        }
+
+        fn event(begins_on: chrono::DateTime<DstZone>, duration: TimeDelta) -> Event {
+            Event {
+                begins_on: DateTime::new(begins_on.to_utc()),
+                ends_on: DateTime::new((begins_on + duration).to_utc()),
+                ..Default::default()
+            }
+        }
+
+        /// Each property is checked in every zone, on instants close to the
+        /// changes of clocks.
+        fn check(property: impl Fn(&mut Random, DstZone)) {
+            let mut random = Random(0x2545_f491_4f6c_dd1d);
+            ZONES.into_iter().for_each(|zone| {
+                (0..CASES).for_each(|_| property(&mut random, zone));
+            });
+        }
+
+        /// What is shown to the user, minutes being rounded up as in the
+        /// interface.
+        fn shown(date_time: HumanReadableDateTime) -> String {
+            match date_time {
+                HumanReadableDateTime::Ongoing { remaining } => {
+                    format!("Ongoing {}", minutes_rounded_up(remaining))
+                }
+                _ => format!("{:?}", date_time),
+            }
+        }
+
+        #[test]
+        fn midnight_skipped_by_clocks_is_when_they_go_forward() {
+            let now = AT_MIDNIGHT.with_ymd_and_hms(2001, 3, 25, 12, 0, 0).unwrap();
+            let midnight = Breakpoints::new(&now).unwrap().midnight;
+            assert_eq!(
+                midnight.naive_local(),
+                NaiveDate::from_ymd_opt(2001, 3, 26)
+                    .unwrap()
+                    .and_hms_opt(1, 0, 0)
+                    .unwrap()
+            );
+            assert_eq!(
+                midnight.to_utc(),
+                chrono::Utc.with_ymd_and_hms(2001, 3, 26, 4, 0, 0).unwrap()
+            );
+        }
+
+        #[test]
+        fn midnight_happening_twice_is_the_first_one() {
+            let now = AT_MIDNIGHT
+                .with_ymd_and_hms(2001, 10, 28, 12, 0, 0)
+                .unwrap();
+            assert_eq!(
+                Breakpoints::new(&now).unwrap().midnight.to_utc(),
+                chrono::Utc.with_ymd_and_hms(2001, 10, 29, 3, 0, 0).unwrap()
+            );
+        }
+
+        #[test]
+        fn days_last_as_long_as_clocks_say() {
+            let now = EUROPE.with_ymd_and_hms(2001, 3, 25, 12, 0, 0).unwrap();
+            let breakpoints = Breakpoints::new(&now).unwrap();
+            assert_eq!(
+                breakpoints.day_after_tomorrow - breakpoints.midnight,
+                TimeDelta::hours(23)
+            );
+            let now = EUROPE.with_ymd_and_hms(2001, 10, 28, 12, 0, 0).unwrap();
+            let breakpoints = Breakpoints::new(&now).unwrap();
+            assert_eq!(
+                breakpoints.day_after_tomorrow - breakpoints.midnight,
+                TimeDelta::hours(25)
+            );
+        }
+
+        #[test]
+        fn breakpoints_are_the_first_instants_of_their_day() {
+            check(|random, zone| {
+                let now = random.around_transition(zone);
+                let breakpoints = Breakpoints::new(&now).unwrap();
+                let today = now.date_naive();
+                [
+                    (breakpoints.midnight, 1),
+                    (breakpoints.day_after_tomorrow, 2),
+                    (breakpoints.next_week, 7),
+                ]
+                .into_iter()
+                .for_each(|(start, days)| {
+                    assert_eq!(start.date_naive(), today + Days::new(days), "{:?}", now);
+                    assert_eq!(
+                        (start - TimeDelta::seconds(1)).date_naive(),
+                        today + Days::new(days - 1),
+                        "{:?}",
+                        now
+                    );
+                });
+                assert!(now < breakpoints.midnight, "{:?}", now);
+                assert!(breakpoints.next_week < breakpoints.next_month, "{:?}", now);
+                assert!(breakpoints.next_month < breakpoints.next_year, "{:?}", now);
+            });
+        }
+
+        #[test]
+        fn human_readable_follows_the_calendar() {
+            check(|random, zone| {
+                let now = random.around_transition(zone);
+                let begins_on = now + TimeDelta::seconds(random.range(3601, 9 * 86400));
+                let days = (begins_on.date_naive() - now.date_naive()).num_days();
+                let shown = event(begins_on, TimeDelta::hours(1)).human_readable_begining_at(&now);
+                match (&shown, days) {
+                    (HumanReadableDateTime::Later(DatePrecision::Time, date_time), 0)
+                    | (HumanReadableDateTime::Tomorrow(date_time), 1)
+                    | (HumanReadableDateTime::Later(DatePrecision::Weekday, date_time), 2..=6)
+                    | (
+                        HumanReadableDateTime::Later(DatePrecision::WeekdayAndDay, date_time),
+                        7..,
+                    ) => {
+                        // Shown with the offset of the day it happens
+                        assert_eq!(date_time.naive_local(), begins_on.naive_local());
+                    }
+                    _ => panic!("{:?} for {:?} at {:?}", shown, begins_on, now),
+                }
+            });
+        }
+
+        #[test]
+        fn sections_hold_until_the_next_section_change() {
+            check(|random, zone| {
+                let now = random.around_transition(zone);
+                let event = event(
+                    now + TimeDelta::seconds(random.range(-3 * 3600, 10 * 86400)),
+                    TimeDelta::seconds(random.range(1800, 3 * 86400)),
+                );
+                let change = event.next_section_change(now).unwrap();
+                assert!(change > now);
+                let later = random.between(now, change);
+                let section: TimelineSection = event.timeline_section(now);
+                assert_eq!(event.timeline_section(later), section, "{:?}", later);
+            });
+        }
+
+        #[test]
+        fn human_readable_holds_until_the_next_change() {
+            check(|random, zone| {
+                let now = random.around_transition(zone);
+                let event = event(
+                    now + TimeDelta::seconds(random.range(-3 * 3600, 10 * 86400)),
+                    TimeDelta::seconds(random.range(1800, 3 * 86400)),
+                );
+                let Some(change) = event.next_human_readable_change(now) else {
+                    assert_eq!(
+                        event.human_readable_begining_at(&now),
+                        HumanReadableDateTime::Ended
+                    );
+                    return;
+                };
+                assert!(change > now);
+                let later = random.between(now, change);
+                assert_eq!(
+                    shown(event.human_readable_begining_at(&later)),
+                    shown(event.human_readable_begining_at(&now)),
+                    "{:?}",
+                    later
+                );
+            });
+        }
+    }
 }
@@ -0,0 +1,139 @@
+use url::Url;
+
+use crate::core::event::Event;
+use crate::core::participation::JoinOptions;
+
+/// Everything known about an event, as shown on its own page.
+#[derive(Debug, Default)]
+pub struct EventDetails {
+    pub event: Event,
+    /// Id of the event in the API, which is not its uuid
+    pub id: String,
+    pub url: Option<Url>,
+    /// Id of the category, e.g. "ARTS"
+    pub category: Option<String>,
+    /// Code of the language the event is held in
+    pub language: Option<String>,
+    pub tags: Vec<String>,
+    pub physical_address: Option<Address>,
+    pub online_address: Option<Url>,
+    pub organizer: Option<Actor>,
+    pub participant_stats: ParticipantStats,
+    pub maximum_attendee_capacity: Option<i64>,
+    pub join_options: JoinOptions,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Address {
+    /// Name of the place, e.g. "Town hall"
+    pub description: Option<String>,
+    pub street: Option<String>,
+    pub postal_code: Option<String>,
+    pub locality: Option<String>,
+    pub region: Option<String>,
+    pub country: Option<String>,
+}
+
+impl Address {
+    /// The postal part of the address on one line, skipping what is missing.
+    pub fn to_single_line(&self) -> String {
+        let town = [&self.postal_code, &self.locality]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .cloned()
+            .collect::<Vec<String>>()
+            .join(" ");
+        [
+            self.street.clone(),
+            Some(town),
+            self.region.clone(),
+            self.country.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<String>>()
+        .join(", ")
+    }
+}
+
+/// A person or group, e.g. the organizer of an event
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Actor {
+    pub name: Option<String>,
+    pub username: String,
+    /// None for actors of the instance itself
+    pub domain: Option<String>,
+}
+
+impl Actor {
+    /// Fediverse handle, such as "@alice@mobilizon.fr"
+    pub fn handle(&self) -> String {
+        match &self.domain {
+            Some(domain) => format!("@{}@{}", self.username, domain),
+            None => format!("@{}", self.username),
+        }
+    }
+
+    pub fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| self.handle())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ParticipantStats {
+    pub going: i64,
+    pub not_approved: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Actor, Address};
+
+    #[test]
+    fn address_skips_missing_parts() {
+        let address = Address {
+            street: Some("1 rue de la Paix".to_string()),
+            postal_code: Some("75002".to_string()),
+            locality: Some("Paris".to_string()),
+            country: Some("".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(address.to_single_line(), "1 rue de la Paix, 75002 Paris");
+    }
+
+    #[test]
+    fn address_without_postal_code() {
+        let address = Address {
+            locality: Some("Lyon".to_string()),
+            country: Some("France".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(address.to_single_line(), "Lyon, France");
+    }
+
+    #[test]
+    fn actor_without_name_shows_its_handle() {
+        let actor = Actor {
+            name: Some("".to_string()),
+            username: "alice".to_string(),
+            domain: Some("example.org".to_string()),
+        };
+        assert_eq!(actor.display_name(), "@alice@example.org");
+    }
+
+    #[test]
+    fn local_actor_handle_has_no_domain() {
+        let actor = Actor {
+            name: Some("Alice".to_string()),
+            username: "alice".to_string(),
+            domain: None,
+        };
+        assert_eq!(actor.handle(), "@alice");
+        assert_eq!(actor.display_name(), "Alice");
+    }
+}
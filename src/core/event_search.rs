@@ -0,0 +1,30 @@
+use crate::core::event::EventStatus;
+
+/// Criteria the timeline is filtered with, pagination aside.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventSearch {
+    /// Full-text search, empty to match every event.
+    pub term: String,
+    /// Ids of the categories events must be in, empty to allow any.
+    pub categories: Vec<String>,
+    /// Codes of the languages events must be in, empty to allow any.
+    pub languages: Vec<String>,
+    /// Codes of the languages whose events come first in the results.
+    pub boost_languages: Vec<String>,
+    /// Statuses events must have, empty to allow any.
+    pub statuses: Vec<EventStatus>,
+    /// Events starting before this are left out, `None` for now. Set once per
+    /// timeline so that events starting while it is paged through do not
+    /// shift the following pages.
+    pub begins_on: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl EventSearch {
+    /// Whether every upcoming event matches, whatever comes first.
+    pub fn is_unfiltered(&self) -> bool {
+        self.term.is_empty()
+            && self.categories.is_empty()
+            && self.languages.is_empty()
+            && self.statuses.is_empty()
+    }
+}
@@ -0,0 +1,605 @@
+use chrono::{FixedOffset, TimeDelta, Utc};
+use std::collections::BTreeMap;
+use url::Url;
+use uuid::Uuid;
+
+use crate::core::event::{Event, EventStatus};
+use crate::core::event_details::EventDetails;
+
+/// Names the application in the calendars it writes.
+const PRODUCT_ID: &str = "-//Mobilisation//Mobilisation//EN";
+
+/// Longest line of a calendar, in octets, line break excluded (RFC 5545
+/// section 3.1).
+const MAX_LINE_LENGTH: usize = 75;
+
+/// An event as written to a calendar file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub uid: Uuid,
+    pub summary: String,
+    pub begins_on: chrono::DateTime<Utc>,
+    pub ends_on: chrono::DateTime<Utc>,
+    pub status: EventStatus,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    pub url: Option<Url>,
+    /// Name of the time zone of the event in the IANA database
+    pub time_zone: Option<String>,
+}
+
+impl From<&Event> for CalendarEvent {
+    fn from(event: &Event) -> Self {
+        CalendarEvent {
+            uid: event.id,
+            summary: event.title.clone(),
+            begins_on: event.begins_on.to_utc(),
+            ends_on: event.ends_on.to_utc(),
+            status: event.status,
+            location: None,
+            description: event.description.clone(),
+            url: None,
+            time_zone: event.time_zone.clone(),
+        }
+    }
+}
+
+impl From<&EventDetails> for CalendarEvent {
+    fn from(details: &EventDetails) -> Self {
+        let place = details.physical_address.as_ref().map(|address| {
+            match address.description.as_ref().filter(|d| !d.is_empty()) {
+                Some(place) => format!("{}, {}", place, address.to_single_line()),
+                None => address.to_single_line(),
+            }
+        });
+        CalendarEvent {
+            location: place
+                .filter(|place| !place.is_empty())
+                .or_else(|| details.online_address.as_ref().map(Url::to_string)),
+            url: details.url.clone(),
+            ..CalendarEvent::from(&details.event)
+        }
+    }
+}
+
+/// A calendar holding `events`, in the iCalendar format of RFC 5545 used
+/// by `.ics` files. `now` is when the calendar is written.
+///
+/// Events are written in their own time zone, `offset_at` telling how far
+/// from UTC a zone is at a given instant. Those in a zone it does not know
+/// are written in UTC.
+pub fn to_icalendar(
+    events: &[CalendarEvent],
+    now: chrono::DateTime<Utc>,
+    offset_at: impl Fn(&str, &chrono::DateTime<Utc>) -> Option<FixedOffset>,
+) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODUCT_ID),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+    ];
+    // Each zone is defined once, for the time all of its events span
+    let mut spans = BTreeMap::<&str, (chrono::DateTime<Utc>, chrono::DateTime<Utc>)>::new();
+    events
+        .iter()
+        .filter_map(|event| Some((event.time_zone.as_deref()?, event)))
+        .filter(|(zone, event)| offset_at(zone, &event.begins_on).is_some())
+        .for_each(|(zone, event)| {
+            let (from, until) = spans
+                .entry(zone)
+                .or_insert((event.begins_on, event.ends_on));
+            *from = (*from).min(event.begins_on);
+            *until = (*until).max(event.ends_on);
+        });
+    spans.iter().for_each(|(zone, (from, until))| {
+        lines.extend(time_zone_lines(zone, *from, *until, &offset_at));
+    });
+    events.iter().for_each(|event| {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", event.uid));
+        lines.push(format!("DTSTAMP:{}", format_date_time(&now)));
+        let zone = event
+            .time_zone
+            .as_deref()
+            .filter(|zone| spans.contains_key(zone));
+        lines.push(time_line("DTSTART", &event.begins_on, zone, &offset_at));
+        lines.push(time_line("DTEND", &event.ends_on, zone, &offset_at));
+        lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+        lines.push(format!("STATUS:{}", event.status.as_str()));
+        if let Some(location) = &event.location {
+            lines.push(format!("LOCATION:{}", escape_text(location)));
+        }
+        if let Some(description) = &event.description {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        // Urls are not text values, they are written as is
+        if let Some(url) = &event.url {
+            lines.push(format!("URL:{}", url));
+        }
+        lines.push("END:VEVENT".to_string());
+    });
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold_line(line) + "\r\n").collect()
+}
+
+/// A property holding `date_time`, written in `zone` if any.
+fn time_line(
+    name: &str,
+    date_time: &chrono::DateTime<Utc>,
+    zone: Option<&str>,
+    offset_at: impl Fn(&str, &chrono::DateTime<Utc>) -> Option<FixedOffset>,
+) -> String {
+    match zone.and_then(|zone| Some((zone, offset_at(zone, date_time)?))) {
+        Some((zone, offset)) => format!(
+            "{};TZID={}:{}",
+            name,
+            parameter_value(zone),
+            format_local_date_time(date_time, offset)
+        ),
+        None => format!("{}:{}", name, format_date_time(date_time)),
+    }
+}
+
+/// Definition of `zone` from `from` to `until`, which calendars need to
+/// read the times written in it (RFC 5545 section 3.6.5). Each change of
+/// offset is an observance of its own, summer time being told apart from
+/// standard time by its larger offset.
+fn time_zone_lines(
+    zone: &str,
+    from: chrono::DateTime<Utc>,
+    until: chrono::DateTime<Utc>,
+    offset_at: impl Fn(&str, &chrono::DateTime<Utc>) -> Option<FixedOffset>,
+) -> Vec<String> {
+    let changes = offset_changes(from, until, |date_time| offset_at(zone, date_time));
+    let standard = changes
+        .iter()
+        .map(|(_, offset)| *offset)
+        .min_by_key(FixedOffset::local_minus_utc);
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", zone)];
+    let mut previous = changes.first().map(|(_, offset)| *offset);
+    changes.iter().for_each(|(date_time, offset)| {
+        let offset_from = previous.unwrap_or(*offset);
+        let kind = if Some(*offset) == standard {
+            "STANDARD"
+        } else {
+            "DAYLIGHT"
+        };
+        lines.push(format!("BEGIN:{}", kind));
+        // Onsets are in the local time before the change
+        lines.push(format!(
+            "DTSTART:{}",
+            format_local_date_time(date_time, offset_from)
+        ));
+        lines.push(format!("TZOFFSETFROM:{}", format_offset(offset_from)));
+        lines.push(format!("TZOFFSETTO:{}", format_offset(*offset)));
+        lines.push(format!("END:{}", kind));
+        previous = Some(*offset);
+    });
+    lines.push("END:VTIMEZONE".to_string());
+    lines
+}
+
+/// Offsets of a zone from `from` to `until`, with the instants they start
+/// at. Clocks are looked at day by day, then to the second
+/// around a change, no zone changing them more than once a day.
+fn offset_changes(
+    from: chrono::DateTime<Utc>,
+    until: chrono::DateTime<Utc>,
+    offset_at: impl Fn(&chrono::DateTime<Utc>) -> Option<FixedOffset>,
+) -> Vec<(chrono::DateTime<Utc>, FixedOffset)> {
+    let Some(first) = offset_at(&from) else {
+        return Vec::new();
+    };
+    let mut changes = vec![(from, first)];
+    let mut current = (from, first);
+    while current.0 < until {
+        let next = (current.0 + TimeDelta::days(1)).min(until);
+        if offset_at(&next).is_none_or(|offset| offset == current.1) {
+            current.0 = next;
+            continue;
+        }
+        let (mut before, mut after) = (current.0, next);
+        while after - before > TimeDelta::seconds(1) {
+            let middle = before + (after - before) / 2;
+            if offset_at(&middle).is_none_or(|offset| offset == current.1) {
+                before = middle;
+            } else {
+                after = middle;
+            }
+        }
+        // The offset was known at `next`, it is known from `after` on
+        current = (after, offset_at(&after).unwrap_or(current.1));
+        changes.push(current);
+    }
+    changes
+}
+
+/// Times are written in UTC, which every calendar understands without a
+/// time zone definition.
+fn format_date_time(date_time: &chrono::DateTime<Utc>) -> String {
+    date_time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Times in a time zone are written as read on a clock there.
+fn format_local_date_time(date_time: &chrono::DateTime<Utc>, offset: FixedOffset) -> String {
+    date_time
+        .with_timezone(&offset)
+        .format("%Y%m%dT%H%M%S")
+        .to_string()
+}
+
+/// Offsets are written in hours and minutes, seconds only when there are
+/// some, as in old local mean times.
+fn format_offset(offset: FixedOffset) -> String {
+    let seconds = offset.local_minus_utc();
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.unsigned_abs();
+    let (hours, minutes) = (seconds / 3600, seconds / 60 % 60);
+    match seconds % 60 {
+        0 => format!("{}{:02}{:02}", sign, hours, minutes),
+        seconds => format!("{}{:02}{:02}{:02}", sign, hours, minutes, seconds),
+    }
+}
+
+/// Parameter values holding a colon, semicolon or comma are quoted.
+fn parameter_value(value: &str) -> String {
+    if value.contains([':', ';', ',']) {
+        format!("\"{}\"", value.replace('"', ""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Splits a line longer than allowed, continuation lines starting with a
+/// space. Multi-byte characters are never cut in half.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut length = 0;
+    for character in line.chars() {
+        if length + character.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(character);
+        length += character.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::event::DateTime;
+    use crate::core::event_details::Address;
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    /// Reads back what `to_icalendar` writes, the way calendar applications
+    /// would.
+    fn parse(calendar: &str) -> Vec<CalendarEvent> {
+        let unfolded = calendar.replace("\r\n ", "");
+        let mut events = Vec::new();
+        let mut current: Option<CalendarEvent> = None;
+        // Observances of each zone, as the instant they start at and the
+        // offset from then on
+        let mut zones = HashMap::<String, Vec<(chrono::DateTime<Utc>, FixedOffset)>>::new();
+        let mut zone = None;
+        let mut onset = None;
+        let mut offset_from = None;
+        for line in unfolded.split("\r\n").filter(|line| !line.is_empty()) {
+            let (name, value) = line.split_once(':').unwrap();
+            let (name, time_zone) = match name.split_once(";TZID=") {
+                Some((name, time_zone)) => (name, Some(time_zone.to_string())),
+                None => (name, None),
+            };
+            if current.is_none() {
+                match name {
+                    "TZID" => zone = Some(value.to_string()),
+                    "DTSTART" => onset = Some(parse_naive_date_time(value)),
+                    "TZOFFSETFROM" => offset_from = Some(parse_offset(value)),
+                    "TZOFFSETTO" => {
+                        let offset_from = offset_from.take().unwrap();
+                        let onset = onset.take().unwrap().and_local_timezone(offset_from);
+                        zones
+                            .entry(zone.clone().unwrap())
+                            .or_default()
+                            .push((onset.unwrap().to_utc(), parse_offset(value)));
+                    }
+                    _ => (),
+                }
+            }
+            let read_time = |value: &str| match &time_zone {
+                Some(time_zone) => parse_local_date_time(value, &zones[time_zone]),
+                None => parse_date_time(value),
+            };
+            match name {
+                "BEGIN" if value == "VEVENT" => {
+                    current = Some(CalendarEvent {
+                        uid: Uuid::nil(),
+                        summary: String::new(),
+                        begins_on: Utc::now(),
+                        ends_on: Utc::now(),
+                        status: EventStatus::Confirmed,
+                        location: None,
+                        description: None,
+                        url: None,
+                        time_zone: None,
+                    })
+                }
+                "END" if value == "VEVENT" => events.push(current.take().unwrap()),
+                _ => {
+                    let Some(event) = current.as_mut() else {
+                        continue;
+                    };
+                    match name {
+                        "UID" => event.uid = Uuid::parse_str(value).unwrap(),
+                        "DTSTART" => {
+                            event.begins_on = read_time(value);
+                            event.time_zone = time_zone;
+                        }
+                        "DTEND" => event.ends_on = read_time(value),
+                        "SUMMARY" => event.summary = unescape_text(value),
+                        "STATUS" => event.status = EventStatus::from_str(value).unwrap(),
+                        "LOCATION" => event.location = Some(unescape_text(value)),
+                        "DESCRIPTION" => event.description = Some(unescape_text(value)),
+                        "URL" => event.url = Some(Url::parse(value).unwrap()),
+                        _ => (),
+                    }
+                }
+            }
+        }
+        events
+    }
+
+    fn parse_date_time(value: &str) -> chrono::DateTime<Utc> {
+        chrono::NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+            .unwrap()
+            .and_utc()
+    }
+
+    fn parse_naive_date_time(value: &str) -> chrono::NaiveDateTime {
+        chrono::NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").unwrap()
+    }
+
+    /// The offset of the last observance started by then applies.
+    fn parse_local_date_time(
+        value: &str,
+        observances: &[(chrono::DateTime<Utc>, FixedOffset)],
+    ) -> chrono::DateTime<Utc> {
+        let local = parse_naive_date_time(value);
+        observances
+            .iter()
+            .rev()
+            .map(|(onset, offset)| (onset, local.and_local_timezone(*offset).unwrap().to_utc()))
+            .find(|(onset, date_time)| *onset <= date_time)
+            .map(|(_, date_time)| date_time)
+            .unwrap()
+    }
+
+    fn parse_offset(value: &str) -> FixedOffset {
+        let sign = if value.starts_with('-') { -1 } else { 1 };
+        let digits = value.as_bytes()[1..]
+            .chunks(2)
+            .map(|pair| std::str::from_utf8(pair).unwrap().parse::<i32>().unwrap())
+            .collect::<Vec<i32>>();
+        let seconds = digits[0] * 3600 + digits[1] * 60 + digits.get(2).unwrap_or(&0);
+        FixedOffset::east_opt(sign * seconds).unwrap()
+    }
+
+    /// Paris, in 2025, going to summer time on March 30 and back on
+    /// October 26.
+    fn offset_at(zone: &str, date_time: &chrono::DateTime<Utc>) -> Option<FixedOffset> {
+        let summer = Utc.with_ymd_and_hms(2025, 3, 30, 1, 0, 0).unwrap()
+            ..Utc.with_ymd_and_hms(2025, 10, 26, 1, 0, 0).unwrap();
+        match zone {
+            "Europe/Paris" if summer.contains(date_time) => FixedOffset::east_opt(7200),
+            "Europe/Paris" => FixedOffset::east_opt(3600),
+            _ => None,
+        }
+    }
+
+    fn unescape_text(value: &str) -> String {
+        let mut text = String::new();
+        let mut characters = value.chars();
+        while let Some(character) = characters.next() {
+            match character {
+                '\\' => match characters.next() {
+                    Some('n') | Some('N') => text.push('\n'),
+                    Some(escaped) => text.push(escaped),
+                    None => (),
+                },
+                _ => text.push(character),
+            }
+        }
+        text
+    }
+
+    fn event() -> CalendarEvent {
+        CalendarEvent {
+            uid: Uuid::parse_str("1e1a7bd4-f7c1-4fb4-92a6-8d5d2c3f4e5a").unwrap(),
+            summary: "Repair café; bring your toaster, kettle \\ lamp".to_string(),
+            begins_on: Utc.with_ymd_and_hms(2025, 3, 8, 13, 30, 0).unwrap(),
+            ends_on: Utc.with_ymd_and_hms(2025, 3, 8, 17, 0, 0).unwrap(),
+            status: EventStatus::Tentative,
+            location: Some("Town hall, 1 rue de la Paix, 75002 Paris".to_string()),
+            description: Some("First line\nSecond line".to_string()),
+            url: Some(Url::parse("https://mobilizon.example/events/1e1a7bd4").unwrap()),
+            time_zone: None,
+        }
+    }
+
+    #[test]
+    fn events_survive_a_round_trip() {
+        let events = vec![
+            event(),
+            CalendarEvent {
+                uid: Uuid::nil(),
+                summary: "Picnic".to_string(),
+                location: None,
+                description: None,
+                url: None,
+                ..event()
+            },
+        ];
+        assert_eq!(parse(&to_icalendar(&events, Utc::now(), offset_at)), events);
+    }
+
+    #[test]
+    fn long_lines_survive_a_round_trip() {
+        let events = vec![CalendarEvent {
+            description: Some("Ça va être une très belle journée ! ".repeat(20)),
+            ..event()
+        }];
+        assert_eq!(parse(&to_icalendar(&events, Utc::now(), offset_at)), events);
+    }
+
+    #[test]
+    fn times_are_written_in_utc() {
+        let calendar = to_icalendar(&[event()], Utc::now(), offset_at);
+        assert!(calendar.contains("\r\nDTSTART:20250308T133000Z\r\n"));
+        assert!(calendar.contains("\r\nDTEND:20250308T170000Z\r\n"));
+    }
+
+    #[test]
+    fn events_in_a_time_zone_survive_a_round_trip() {
+        let events = vec![
+            CalendarEvent {
+                time_zone: Some("Europe/Paris".to_string()),
+                ..event()
+            },
+            // Clocks go forward during the night
+            CalendarEvent {
+                time_zone: Some("Europe/Paris".to_string()),
+                begins_on: Utc.with_ymd_and_hms(2025, 3, 29, 20, 0, 0).unwrap(),
+                ends_on: Utc.with_ymd_and_hms(2025, 3, 30, 8, 0, 0).unwrap(),
+                ..event()
+            },
+            event(),
+        ];
+        assert_eq!(parse(&to_icalendar(&events, Utc::now(), offset_at)), events);
+    }
+
+    #[test]
+    fn times_are_written_in_the_time_zone_of_the_event() {
+        let events = vec![CalendarEvent {
+            time_zone: Some("Europe/Paris".to_string()),
+            ends_on: Utc.with_ymd_and_hms(2025, 4, 8, 17, 0, 0).unwrap(),
+            ..event()
+        }];
+        let calendar = to_icalendar(&events, Utc::now(), offset_at);
+        assert!(calendar.contains("\r\nDTSTART;TZID=Europe/Paris:20250308T143000\r\n"));
+        assert!(calendar.contains("\r\nDTEND;TZID=Europe/Paris:20250408T190000\r\n"));
+        assert!(calendar.contains(concat!(
+            "\r\nBEGIN:VTIMEZONE\r\n",
+            "TZID:Europe/Paris\r\n",
+            "BEGIN:STANDARD\r\n",
+            "DTSTART:20250308T143000\r\n",
+            "TZOFFSETFROM:+0100\r\n",
+            "TZOFFSETTO:+0100\r\n",
+            "END:STANDARD\r\n",
+            "BEGIN:DAYLIGHT\r\n",
+            "DTSTART:20250330T020000\r\n",
+            "TZOFFSETFROM:+0100\r\n",
+            "TZOFFSETTO:+0200\r\n",
+            "END:DAYLIGHT\r\n",
+            "END:VTIMEZONE\r\n",
+        )));
+    }
+
+    #[test]
+    fn unknown_time_zones_are_left_for_utc() {
+        let events = vec![CalendarEvent {
+            time_zone: Some("Atlantis/Poseidonia".to_string()),
+            ..event()
+        }];
+        let calendar = to_icalendar(&events, Utc::now(), offset_at);
+        assert!(calendar.contains("\r\nDTSTART:20250308T133000Z\r\n"));
+        assert!(!calendar.contains("VTIMEZONE"));
+    }
+
+    #[test]
+    fn offsets_are_written_in_hours_and_minutes() {
+        let offset = |seconds| format_offset(FixedOffset::east_opt(seconds).unwrap());
+        assert_eq!(offset(0), "+0000");
+        assert_eq!(offset(19800), "+0530");
+        assert_eq!(offset(-12600), "-0330");
+        assert_eq!(offset(561), "+000921");
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        let calendar = to_icalendar(&[event()], Utc::now(), offset_at);
+        assert!(calendar.contains("SUMMARY:Repair café\\; bring your toaster\\, kettle \\\\ lamp"));
+        assert!(calendar.contains("DESCRIPTION:First line\\nSecond line"));
+    }
+
+    #[test]
+    fn lines_are_folded_at_75_octets() {
+        let events = vec![CalendarEvent {
+            summary: "é".repeat(100),
+            ..event()
+        }];
+        let calendar = to_icalendar(&events, Utc::now(), offset_at);
+        assert!(
+            calendar
+                .split("\r\n")
+                .all(|line| line.len() <= MAX_LINE_LENGTH)
+        );
+        assert!(calendar.contains("\r\n é"));
+    }
+
+    #[test]
+    fn calendar_is_wrapped_and_stamped() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap();
+        let calendar = to_icalendar(&[event()], now, offset_at);
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert!(calendar.contains("\r\nDTSTAMP:20250102T030405Z\r\n"));
+        assert!(calendar.contains("\r\nUID:1e1a7bd4-f7c1-4fb4-92a6-8d5d2c3f4e5a\r\n"));
+    }
+
+    #[test]
+    fn details_fill_the_location() {
+        let details = EventDetails {
+            event: Event {
+                begins_on: DateTime::new(Utc::now()),
+                ends_on: DateTime::new(Utc::now()),
+                ..Default::default()
+            },
+            physical_address: Some(Address {
+                description: Some("Town hall".to_string()),
+                locality: Some("Lyon".to_string()),
+                ..Default::default()
+            }),
+            online_address: Some(Url::parse("https://meet.example/room").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(
+            CalendarEvent::from(&details).location.as_deref(),
+            Some("Town hall, Lyon")
+        );
+    }
+
+    #[test]
+    fn online_events_are_located_at_their_url() {
+        let details = EventDetails {
+            online_address: Some(Url::parse("https://meet.example/room").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(
+            CalendarEvent::from(&details).location.as_deref(),
+            Some("https://meet.example/room")
+        );
+    }
+}
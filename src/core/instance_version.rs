@@ -6,13 +6,27 @@ pub enum InstanceVersionParsingError {
     ParseError(String),
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct InstanceVersion {
     pub major: i32,
     pub minor: i32,
     pub patch: i32,
 }
 
+/// Oldest Mobilizon release exposing everything the queries rely on
+/// (event categories, `searchEvents` with `longEvents`).
+pub const MINIMUM_SUPPORTED_VERSION: InstanceVersion = InstanceVersion {
+    major: 3,
+    minor: 0,
+    patch: 0,
+};
+
+impl InstanceVersion {
+    pub fn is_supported(&self) -> bool {
+        *self >= MINIMUM_SUPPORTED_VERSION
+    }
+}
+
 impl FromStr for InstanceVersion {
     type Err = InstanceVersionParsingError;
 
@@ -30,3 +44,39 @@ impl FromStr for InstanceVersion {
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::InstanceVersion;
+
+    #[test]
+    fn versions_are_ordered_component_wise() {
+        let a = InstanceVersion::from_str("3.2.10").ok().unwrap();
+        let b = InstanceVersion::from_str("3.10.0").ok().unwrap();
+        assert!(a < b);
+    }
+
+    #[test]
+    fn old_versions_are_not_supported() {
+        assert!(
+            !InstanceVersion::from_str("2.1.0")
+                .ok()
+                .unwrap()
+                .is_supported()
+        );
+        assert!(
+            InstanceVersion::from_str("3.0.0")
+                .ok()
+                .unwrap()
+                .is_supported()
+        );
+        assert!(
+            InstanceVersion::from_str("5.1.4")
+                .ok()
+                .unwrap()
+                .is_supported()
+        );
+    }
+}
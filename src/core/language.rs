@@ -0,0 +1,129 @@
+#[derive(Debug, Clone, PartialEq)]
+pub struct Language {
+    /// ISO 639 code, as used by Mobilizon (e.g. "fr", "pt_BR")
+    pub code: String,
+}
+
+/// Names of the languages in their own language, so that they read the same
+/// whatever the language of the interface.
+const ENDONYMS: &[(&str, &str)] = &[
+    ("ar", "العربية"),
+    ("be", "Беларуская"),
+    ("bn", "বাংলা"),
+    ("br", "Brezhoneg"),
+    ("ca", "Català"),
+    ("co", "Corsu"),
+    ("cs", "Čeština"),
+    ("cy", "Cymraeg"),
+    ("da", "Dansk"),
+    ("de", "Deutsch"),
+    ("el", "Ελληνικά"),
+    ("en", "English"),
+    ("eo", "Esperanto"),
+    ("es", "Español"),
+    ("eu", "Euskara"),
+    ("fa", "فارسی"),
+    ("fi", "Suomi"),
+    ("fr", "Français"),
+    ("ga", "Gaeilge"),
+    ("gd", "Gàidhlig"),
+    ("gl", "Galego"),
+    ("he", "עברית"),
+    ("hr", "Hrvatski"),
+    ("hu", "Magyar"),
+    ("id", "Bahasa Indonesia"),
+    ("it", "Italiano"),
+    ("ja", "日本語"),
+    ("kab", "Taqbaylit"),
+    ("ko", "한국어"),
+    ("lt", "Lietuvių"),
+    ("nb", "Norsk bokmål"),
+    ("nl", "Nederlands"),
+    ("nn", "Norsk nynorsk"),
+    ("oc", "Occitan"),
+    ("pl", "Polski"),
+    ("pt", "Português"),
+    ("pt_BR", "Português (Brasil)"),
+    ("ro", "Română"),
+    ("ru", "Русский"),
+    ("sk", "Slovenčina"),
+    ("sl", "Slovenščina"),
+    ("sq", "Shqip"),
+    ("sr", "Српски"),
+    ("sv", "Svenska"),
+    ("tr", "Türkçe"),
+    ("uk", "Українська"),
+    ("vi", "Tiếng Việt"),
+    ("zh", "中文"),
+    ("zh_Hant", "中文（繁體）"),
+];
+
+impl Language {
+    pub fn new(code: &str) -> Self {
+        Language {
+            code: code.to_string(),
+        }
+    }
+
+    /// Human readable name, falling back on the code for unknown languages.
+    pub fn name(&self) -> String {
+        ENDONYMS
+            .iter()
+            .find(|(code, _)| *code == self.code)
+            .map(|(_, name)| name.to_string())
+            .unwrap_or_else(|| self.code.clone())
+    }
+}
+
+/// Language codes out of locale names such as the ones returned by
+/// `g_get_language_names` ("fr_FR.UTF-8", "fr_FR", "fr", "C"), most
+/// preferred first and without duplicates.
+pub fn language_codes_from_locales<S: AsRef<str>>(locales: &[S]) -> Vec<String> {
+    let mut codes: Vec<String> = Vec::new();
+    locales
+        .iter()
+        .map(|locale| {
+            locale
+                .as_ref()
+                .split(['_', '.', '@', '-'])
+                .next()
+                .unwrap_or_default()
+                .to_lowercase()
+        })
+        .filter(|code| !code.is_empty() && code != "c" && code != "posix")
+        .for_each(|code| {
+            if !codes.contains(&code) {
+                codes.push(code);
+            }
+        });
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Language, language_codes_from_locales};
+
+    #[test]
+    fn known_languages_have_a_name() {
+        assert_eq!(Language::new("fr").name(), "Français");
+        assert_eq!(Language::new("pt_BR").name(), "Português (Brasil)");
+    }
+
+    #[test]
+    fn unknown_languages_fall_back_on_their_code() {
+        assert_eq!(Language::new("xx").name(), "xx");
+    }
+
+    #[test]
+    fn locale_names_are_reduced_to_language_codes() {
+        assert_eq!(
+            language_codes_from_locales(&["fr_FR.UTF-8", "fr_FR", "fr", "en_GB", "C"]),
+            vec!["fr".to_string(), "en".to_string()]
+        );
+    }
+
+    #[test]
+    fn posix_locale_has_no_language() {
+        assert!(language_codes_from_locales(&["POSIX", "C.UTF-8"]).is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Map holding at most `capacity` values, forgetting the least recently
+/// used one to make room for a new one.
+#[derive(Debug)]
+pub struct Lru<K, V> {
+    capacity: usize,
+    entries: HashMap<K, (V, u64)>,
+    /// Bumped on every access, the smallest stamp is the least recently used
+    clock: u64,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Lru<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Lru {
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries.get_mut(key).map(|(value, used_at)| {
+            *used_at = clock;
+            value.clone()
+        })
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.clock += 1;
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used_at))| *used_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        if self.capacity > 0 {
+            self.entries.insert(key, (value, self.clock));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lru;
+
+    #[test]
+    fn keeps_values_up_to_capacity() {
+        let mut lru = Lru::new(2);
+        lru.insert("a", 1);
+        lru.insert("b", 2);
+        assert_eq!(lru.get(&"a"), Some(1));
+        assert_eq!(lru.get(&"b"), Some(2));
+    }
+
+    #[test]
+    fn forgets_the_least_recently_used() {
+        let mut lru = Lru::new(2);
+        lru.insert("a", 1);
+        lru.insert("b", 2);
+        lru.get(&"a");
+        lru.insert("c", 3);
+        assert_eq!(lru.get(&"b"), None);
+        assert_eq!(lru.get(&"a"), Some(1));
+        assert_eq!(lru.get(&"c"), Some(3));
+    }
+
+    #[test]
+    fn replacing_a_value_does_not_evict() {
+        let mut lru = Lru::new(2);
+        lru.insert("a", 1);
+        lru.insert("b", 2);
+        lru.insert("a", 10);
+        assert_eq!(lru.get(&"a"), Some(10));
+        assert_eq!(lru.get(&"b"), Some(2));
+    }
+
+    #[test]
+    fn zero_capacity_keeps_nothing() {
+        let mut lru = Lru::new(0);
+        lru.insert("a", 1);
+        assert_eq!(lru.get(&"a"), None);
+    }
+}
@@ -1,3 +1,10 @@
 pub mod instance_version;
 pub mod category;
 pub mod event;
+pub mod event_details;
+pub mod event_search;
+pub mod icalendar;
+pub mod language;
+pub mod lru;
+pub mod pagination;
+pub mod participation;
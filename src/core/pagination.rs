@@ -0,0 +1,88 @@
+/// Number of events requested per page when none is specified.
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+
+/// Keeps track of how far into a paginated search the timeline has loaded.
+/// Pages are numbered from 1, like in the Mobilizon API.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pagination {
+    pub limit: i64,
+    pub pages_loaded: i64,
+    pub total: Option<i64>,
+    exhausted: bool,
+}
+
+impl Pagination {
+    pub fn new(limit: i64) -> Self {
+        Pagination {
+            limit,
+            pages_loaded: 0,
+            total: None,
+            exhausted: false,
+        }
+    }
+
+    /// The page to request next, or None when everything has been loaded.
+    pub fn next_page(&self) -> Option<i64> {
+        if self.is_exhausted() {
+            None
+        } else {
+            Some(self.pages_loaded + 1)
+        }
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    /// Records a page that came back with `received` elements out of `total`.
+    /// A short page also ends the pagination so a wrong total from the server
+    /// can not make us request empty pages forever.
+    pub fn page_loaded(&mut self, received: usize, total: i64) {
+        self.pages_loaded += 1;
+        self.total = Some(total);
+        self.exhausted = (received as i64) < self.limit || self.pages_loaded * self.limit >= total;
+    }
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Pagination::new(DEFAULT_PAGE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pagination;
+
+    #[test]
+    fn first_page_is_one() {
+        assert_eq!(Pagination::new(10).next_page(), Some(1));
+    }
+
+    #[test]
+    fn pages_follow_each_other_until_total() {
+        let mut pagination = Pagination::new(10);
+        pagination.page_loaded(10, 25);
+        assert_eq!(pagination.next_page(), Some(2));
+        pagination.page_loaded(10, 25);
+        assert_eq!(pagination.next_page(), Some(3));
+        pagination.page_loaded(5, 25);
+        assert_eq!(pagination.next_page(), None);
+        assert!(pagination.is_exhausted());
+    }
+
+    #[test]
+    fn exact_multiple_of_limit_ends_without_an_empty_page() {
+        let mut pagination = Pagination::new(10);
+        pagination.page_loaded(10, 20);
+        pagination.page_loaded(10, 20);
+        assert_eq!(pagination.next_page(), None);
+    }
+
+    #[test]
+    fn short_page_ends_even_if_total_says_otherwise() {
+        let mut pagination = Pagination::new(10);
+        pagination.page_loaded(3, 100);
+        assert!(pagination.is_exhausted());
+    }
+}
@@ -0,0 +1,271 @@
+use std::str::FromStr;
+use url::Url;
+
+use crate::core::event::{DateTime, Event};
+use crate::core::event_details::ParticipantStats;
+
+/// How people can take part in an event
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum JoinOptions {
+    /// Anyone joining is a participant straight away
+    #[default]
+    Free,
+    /// Participants have to be approved by the organizer
+    Restricted,
+    /// Only people invited by the organizer can take part
+    Invite,
+    /// Participation is handled on another website
+    External(Option<Url>),
+}
+
+impl JoinOptions {
+    /// Role the user is expected to get when joining, shown before the
+    /// server confirms it.
+    pub fn expected_role(&self) -> Option<ParticipantRole> {
+        match self {
+            JoinOptions::Free => Some(ParticipantRole::Participant),
+            JoinOptions::Restricted => Some(ParticipantRole::NotApproved),
+            JoinOptions::Invite | JoinOptions::External(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticipantRole {
+    NotApproved,
+    NotConfirmed,
+    Rejected,
+    Participant,
+    Moderator,
+    Administrator,
+    Creator,
+}
+
+impl ParticipantRole {
+    pub const ALL: [ParticipantRole; 7] = [
+        ParticipantRole::NotApproved,
+        ParticipantRole::NotConfirmed,
+        ParticipantRole::Rejected,
+        ParticipantRole::Participant,
+        ParticipantRole::Moderator,
+        ParticipantRole::Administrator,
+        ParticipantRole::Creator,
+    ];
+
+    /// Name of the role in the Mobilizon API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParticipantRole::NotApproved => "NOT_APPROVED",
+            ParticipantRole::NotConfirmed => "NOT_CONFIRMED",
+            ParticipantRole::Rejected => "REJECTED",
+            ParticipantRole::Participant => "PARTICIPANT",
+            ParticipantRole::Moderator => "MODERATOR",
+            ParticipantRole::Administrator => "ADMINISTRATOR",
+            ParticipantRole::Creator => "CREATOR",
+        }
+    }
+
+    pub fn is_organizer(&self) -> bool {
+        matches!(
+            self,
+            ParticipantRole::Moderator | ParticipantRole::Administrator | ParticipantRole::Creator
+        )
+    }
+
+    /// Counted in the number of people going to the event
+    pub fn is_going(&self) -> bool {
+        matches!(
+            self,
+            ParticipantRole::Participant
+                | ParticipantRole::Moderator
+                | ParticipantRole::Administrator
+                | ParticipantRole::Creator
+        )
+    }
+
+    /// Organizers cannot leave their own event
+    pub fn can_leave(&self) -> bool {
+        matches!(
+            self,
+            ParticipantRole::Participant
+                | ParticipantRole::NotApproved
+                | ParticipantRole::NotConfirmed
+        )
+    }
+}
+
+impl FromStr for ParticipantRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ParticipantRole::ALL
+            .into_iter()
+            .find(|role| role.as_str() == s)
+            .ok_or(s.to_string())
+    }
+}
+
+/// Where the logged in user stands regarding an event
+#[derive(Debug, Clone, PartialEq)]
+pub struct Participation {
+    /// Id of the profile of the user, which is the one joining
+    pub actor_id: String,
+    /// `None` when not taking part in the event
+    pub role: Option<ParticipantRole>,
+}
+
+/// An event the logged in user takes part in or organizes
+#[derive(Debug, Clone)]
+pub struct EventParticipation {
+    pub event: Event,
+    pub role: ParticipantRole,
+}
+
+/// Splits the events of the user into the ones still to come, soonest
+/// first, and the ones that are over, latest first.
+pub fn split_upcoming(
+    mut participations: Vec<EventParticipation>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> (Vec<EventParticipation>, Vec<EventParticipation>) {
+    let now = DateTime::new(now);
+    participations.sort_by(|a, b| a.event.begins_on.cmp(&b.event.begins_on));
+    let (upcoming, mut past): (Vec<_>, Vec<_>) = participations
+        .into_iter()
+        .partition(|participation| participation.event.ends_on >= now);
+    past.reverse();
+    (upcoming, past)
+}
+
+impl ParticipantStats {
+    /// Stats once the role of the user changed, used to update them before
+    /// the server answers.
+    pub fn with_role_change(
+        &self,
+        from: Option<ParticipantRole>,
+        to: Option<ParticipantRole>,
+    ) -> ParticipantStats {
+        let count = |role: Option<ParticipantRole>| -> (i64, i64) {
+            match role {
+                Some(role) if role.is_going() => (1, 0),
+                Some(ParticipantRole::NotApproved) => (0, 1),
+                _ => (0, 0),
+            }
+        };
+        let (going_before, waiting_before) = count(from);
+        let (going_after, waiting_after) = count(to);
+        ParticipantStats {
+            going: (self.going - going_before + going_after).max(0),
+            not_approved: (self.not_approved - waiting_before + waiting_after).max(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restricted_events_need_approval() {
+        assert_eq!(
+            JoinOptions::Restricted.expected_role(),
+            Some(ParticipantRole::NotApproved)
+        );
+        assert_eq!(
+            JoinOptions::Free.expected_role(),
+            Some(ParticipantRole::Participant)
+        );
+        assert_eq!(JoinOptions::External(None).expected_role(), None);
+    }
+
+    #[test]
+    fn joining_counts_the_user_as_going() {
+        let stats = ParticipantStats {
+            going: 3,
+            not_approved: 1,
+        };
+        assert_eq!(
+            stats.with_role_change(None, Some(ParticipantRole::Participant)),
+            ParticipantStats {
+                going: 4,
+                not_approved: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn leaving_a_pending_request_only_changes_the_waiting_list() {
+        let stats = ParticipantStats {
+            going: 3,
+            not_approved: 1,
+        };
+        assert_eq!(
+            stats.with_role_change(Some(ParticipantRole::NotApproved), None),
+            ParticipantStats {
+                going: 3,
+                not_approved: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn stats_never_go_negative() {
+        let stats = ParticipantStats::default();
+        assert_eq!(
+            stats.with_role_change(Some(ParticipantRole::Participant), None),
+            ParticipantStats::default()
+        );
+    }
+
+    fn participation(begins_on: &str, ends_on: &str) -> EventParticipation {
+        EventParticipation {
+            event: Event {
+                title: begins_on.to_string(),
+                begins_on: DateTime::new(begins_on.parse().unwrap()),
+                ends_on: DateTime::new(ends_on.parse().unwrap()),
+                ..Default::default()
+            },
+            role: ParticipantRole::Participant,
+        }
+    }
+
+    #[test]
+    fn events_are_split_around_now() {
+        let now = "2025-06-15T12:00:00Z".parse().unwrap();
+        let (upcoming, past) = split_upcoming(
+            vec![
+                participation("2025-06-01T10:00:00Z", "2025-06-01T12:00:00Z"),
+                participation("2025-07-01T10:00:00Z", "2025-07-01T12:00:00Z"),
+                participation("2025-06-15T10:00:00Z", "2025-06-15T14:00:00Z"),
+                participation("2025-05-01T10:00:00Z", "2025-05-01T12:00:00Z"),
+            ],
+            now,
+        );
+        let titles = |events: Vec<EventParticipation>| {
+            events
+                .into_iter()
+                .map(|participation| participation.event.title)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            titles(upcoming),
+            vec!["2025-06-15T10:00:00Z", "2025-07-01T10:00:00Z"]
+        );
+        assert_eq!(
+            titles(past),
+            vec!["2025-06-01T10:00:00Z", "2025-05-01T10:00:00Z"]
+        );
+    }
+
+    #[test]
+    fn role_round_trips_through_its_api_name() {
+        ParticipantRole::ALL.iter().for_each(|role| {
+            assert_eq!(ParticipantRole::from_str(role.as_str()), Ok(*role));
+        });
+    }
+
+    #[test]
+    fn organizers_cannot_leave() {
+        assert!(!ParticipantRole::Creator.can_leave());
+        assert!(ParticipantRole::NotApproved.can_leave());
+    }
+}
@@ -0,0 +1,355 @@
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::gettext;
+use gtk::glib::translate::IntoGlib;
+use gtk::{gio, glib};
+use std::cell::RefCell;
+use std::sync::Arc;
+use url::Url;
+
+use crate::infra::client::MobilizonApi;
+use crate::pictures::{PictureFit, decode_picture};
+use crate::presentation::html::{Block, BlockKind, Inline, Style, parse_html};
+use crate::runtime;
+
+/// Widest a picture of a description is shown, in logical pixels.
+const MAX_PICTURE_WIDTH: i32 = 480;
+
+/// Links each get a tag named after where they lead, behind this prefix.
+const LINK_TAG_PREFIX: &str = "link:";
+
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct MobilisationDescriptionView {
+        /// Where the pictures of the description are downloaded from
+        pub api: RefCell<Option<Arc<dyn MobilizonApi>>>,
+        /// Pictures are only downloaded by themselves from the instance, as
+        /// those from elsewhere could tell who reads the description
+        pub instance_url: RefCell<Option<Url>>,
+        pub picture_loads: RefCell<Vec<tokio::task::AbortHandle>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MobilisationDescriptionView {
+        const NAME: &'static str = "MobilisationDescriptionView";
+        type Type = super::MobilisationDescriptionView;
+        type ParentType = gtk::TextView;
+    }
+
+    impl ObjectImpl for MobilisationDescriptionView {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+            obj.set_editable(false);
+            obj.set_cursor_visible(false);
+            obj.set_wrap_mode(gtk::WrapMode::WordChar);
+            obj.create_tags();
+            let click = gtk::GestureClick::new();
+            click.connect_released(glib::clone!(
+                #[weak]
+                obj,
+                move |_, _, x, y| {
+                    // Selecting text across a link does not follow it
+                    if obj.buffer().has_selection() {
+                        return;
+                    }
+                    if let Some(url) = obj.link_at(x, y) {
+                        obj.open_url(&url);
+                    }
+                }
+            ));
+            obj.add_controller(click);
+            let motion = gtk::EventControllerMotion::new();
+            motion.connect_motion(glib::clone!(
+                #[weak]
+                obj,
+                move |_, x, y| {
+                    let cursor = match obj.link_at(x, y) {
+                        Some(_) => "pointer",
+                        None => "text",
+                    };
+                    obj.set_cursor_from_name(Some(cursor));
+                }
+            ));
+            obj.add_controller(motion);
+        }
+
+        fn dispose(&self) {
+            self.obj().cancel_picture_loads();
+        }
+    }
+    impl WidgetImpl for MobilisationDescriptionView {}
+    impl TextViewImpl for MobilisationDescriptionView {}
+}
+
+glib::wrapper! {
+    /// Shows the description of an event, written in HTML by its organizer,
+    /// with its headings, lists, emphasis, links and pictures.
+    pub struct MobilisationDescriptionView(ObjectSubclass<imp::MobilisationDescriptionView>)
+    @extends gtk::TextView, gtk::Widget,
+    @implements gtk::Buildable, gtk::Scrollable;
+}
+
+impl MobilisationDescriptionView {
+    pub fn set_api(&self, api: Option<Arc<dyn MobilizonApi>>, instance_url: Option<Url>) {
+        self.imp().api.replace(api);
+        self.imp().instance_url.replace(instance_url);
+    }
+
+    /// Replaces what is shown by `html`, sanitized by `parse_html`.
+    pub fn set_html(&self, html: &str) {
+        self.cancel_picture_loads();
+        let buffer = self.buffer();
+        buffer.set_text("");
+        let mut iter = buffer.end_iter();
+        let blocks = parse_html(html);
+        blocks.iter().enumerate().for_each(|(index, block)| {
+            if index > 0 {
+                buffer.insert(&mut iter, "\n");
+            }
+            let start = iter.offset();
+            if let BlockKind::ListItem { marker, .. } = &block.kind {
+                buffer.insert(&mut iter, &format!("{} ", marker));
+            }
+            self.insert_content(&mut iter, block);
+            let block_tag = self.block_tag(&block.kind);
+            buffer.apply_tag(&block_tag, &buffer.iter_at_offset(start), &iter);
+        });
+    }
+
+    fn create_tags(&self) {
+        let table = self.buffer().tag_table();
+        let tags = [
+            gtk::TextTag::builder()
+                .name("bold")
+                .weight(gtk::pango::Weight::Bold.into_glib())
+                .build(),
+            gtk::TextTag::builder()
+                .name("italic")
+                .style(gtk::pango::Style::Italic)
+                .build(),
+            gtk::TextTag::builder()
+                .name("paragraph")
+                .pixels_below_lines(12)
+                .build(),
+        ];
+        tags.iter().for_each(|tag| {
+            table.add(tag);
+        });
+        (1..=6).for_each(|level| {
+            let scale = match level {
+                1 => gtk::pango::SCALE_X_LARGE,
+                2 => gtk::pango::SCALE_LARGE,
+                _ => gtk::pango::SCALE_MEDIUM,
+            };
+            table.add(
+                &gtk::TextTag::builder()
+                    .name(format!("heading-{}", level))
+                    .weight(gtk::pango::Weight::Bold.into_glib())
+                    .scale(scale)
+                    .pixels_above_lines(6)
+                    .pixels_below_lines(6)
+                    .build(),
+            );
+        });
+    }
+
+    /// Tags giving blocks their spacing, and items of lists their indent.
+    fn block_tag(&self, kind: &BlockKind) -> gtk::TextTag {
+        let table = self.buffer().tag_table();
+        let name = match kind {
+            BlockKind::Paragraph => "paragraph".to_string(),
+            BlockKind::Heading(level) => format!("heading-{}", (*level).clamp(1, 6)),
+            BlockKind::ListItem { depth, .. } => format!("list-{}", depth),
+        };
+        table.lookup(&name).unwrap_or_else(|| {
+            let depth = match kind {
+                BlockKind::ListItem { depth, .. } => *depth as i32,
+                _ => 0,
+            };
+            let tag = gtk::TextTag::builder()
+                .name(name.as_str())
+                .left_margin(18 * depth)
+                .pixels_below_lines(6)
+                .build();
+            table.add(&tag);
+            tag
+        })
+    }
+
+    fn link_tag(&self, url: &Url) -> gtk::TextTag {
+        let table = self.buffer().tag_table();
+        let name = format!("{}{}", LINK_TAG_PREFIX, url);
+        table.lookup(&name).unwrap_or_else(|| {
+            let style_manager = adw::StyleManager::default();
+            let tag = gtk::TextTag::builder()
+                .name(name.as_str())
+                .underline(gtk::pango::Underline::Single)
+                .foreground_rgba(
+                    &style_manager
+                        .accent_color()
+                        .to_standalone_rgba(style_manager.is_dark()),
+                )
+                .build();
+            table.add(&tag);
+            tag
+        })
+    }
+
+    fn insert_content(&self, iter: &mut gtk::TextIter, block: &Block) {
+        let buffer = self.buffer();
+        block.content.iter().for_each(|inline| match inline {
+            Inline::Text { text, style } => {
+                let tags = self.style_tags(style);
+                buffer.insert_with_tags(iter, text, &tags.iter().collect::<Vec<_>>());
+            }
+            Inline::Image { url, alt } => {
+                let anchor = buffer.create_child_anchor(iter);
+                let picture = gtk::Picture::builder()
+                    .can_shrink(true)
+                    .content_fit(gtk::ContentFit::Contain)
+                    .visible(false)
+                    .build();
+                if !alt.is_empty() {
+                    picture.set_alternative_text(Some(alt));
+                    picture.set_tooltip_text(Some(alt));
+                }
+                if self.is_on_instance(url) {
+                    self.add_child_at_anchor(&picture, &anchor);
+                    self.load_picture(&picture, url.clone());
+                } else {
+                    let child = gtk::Box::new(gtk::Orientation::Vertical, 0);
+                    let button = self.picture_button(url);
+                    button.connect_clicked(glib::clone!(
+                        #[weak(rename_to = obj)]
+                        self,
+                        #[weak]
+                        picture,
+                        #[strong]
+                        url,
+                        move |button| {
+                            button.set_visible(false);
+                            obj.load_picture(&picture, url.clone());
+                        }
+                    ));
+                    child.append(&button);
+                    child.append(&picture);
+                    self.add_child_at_anchor(&child, &anchor);
+                }
+            }
+        });
+    }
+
+    fn style_tags(&self, style: &Style) -> Vec<gtk::TextTag> {
+        let table = self.buffer().tag_table();
+        [
+            style.bold.then(|| table.lookup("bold")).flatten(),
+            style.italic.then(|| table.lookup("italic")).flatten(),
+            style.link.as_ref().map(|url| self.link_tag(url)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Where the link at `x`, `y` in the widget leads, if there is one.
+    fn link_at(&self, x: f64, y: f64) -> Option<Url> {
+        let (x, y) = self.window_to_buffer_coords(gtk::TextWindowType::Widget, x as i32, y as i32);
+        self.iter_at_location(x, y)?
+            .tags()
+            .iter()
+            .filter_map(|tag| tag.name())
+            .find_map(|name| Url::parse(name.strip_prefix(LINK_TAG_PREFIX)?).ok())
+    }
+
+    fn open_url(&self, url: &Url) {
+        gtk::UriLauncher::new(url.as_str()).launch(
+            self.root().and_downcast_ref::<gtk::Window>(),
+            gio::Cancellable::NONE,
+            |result| {
+                if let Err(err) = result {
+                    glib::g_log!(glib::LogLevel::Warning, "Error opening url : {}", err);
+                }
+            },
+        );
+    }
+
+    fn is_on_instance(&self, url: &Url) -> bool {
+        self.imp()
+            .instance_url
+            .borrow()
+            .as_ref()
+            .is_some_and(|instance_url| {
+                instance_url.scheme() == url.scheme() && instance_url.host() == url.host()
+            })
+    }
+
+    /// Button standing for a picture from elsewhere than the instance,
+    /// downloading it when clicked.
+    fn picture_button(&self, url: &Url) -> gtk::Button {
+        let label = gettext("Show Picture From {host}")
+            .replace("{host}", url.host_str().unwrap_or_default());
+        gtk::Button::builder()
+            .child(
+                &adw::ButtonContent::builder()
+                    .icon_name("image-x-generic-symbolic")
+                    .label(label)
+                    .build(),
+            )
+            .halign(gtk::Align::Start)
+            .build()
+    }
+
+    /// Pictures show up once downloaded, and stay hidden if they cannot be.
+    fn load_picture(&self, picture: &gtk::Picture, url: Url) {
+        let Some(api) = self.imp().api.borrow().clone() else {
+            return;
+        };
+        let (sender, receiver) = async_channel::unbounded();
+        let picture_load = runtime().spawn(async move {
+            let _ = sender.send(api.fetch_event_picture(&url).await).await;
+        });
+        self.imp()
+            .picture_loads
+            .borrow_mut()
+            .push(picture_load.abort_handle());
+        let scale = self.scale_factor();
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            picture,
+            async move {
+                let bytes = match receiver.recv().await {
+                    Ok(Ok(bytes)) => glib::Bytes::from_owned(bytes),
+                    Ok(Err(err)) => {
+                        glib::g_log!(glib::LogLevel::Warning, "Error fetching picture : {}", err);
+                        return;
+                    }
+                    // The load was cancelled
+                    Err(_) => return,
+                };
+                let fit = PictureFit::Width(MAX_PICTURE_WIDTH * scale);
+                match gio::spawn_blocking(move || decode_picture(&bytes, fit)).await {
+                    Ok(Ok(texture)) => {
+                        picture.set_size_request(texture.width() / scale, texture.height() / scale);
+                        picture.set_paintable(Some(&texture));
+                        picture.set_visible(true);
+                    }
+                    Ok(Err(err)) => {
+                        glib::g_log!(glib::LogLevel::Warning, "Error decoding picture : {}", err);
+                    }
+                    Err(_) => (),
+                }
+            }
+        ));
+    }
+
+    fn cancel_picture_loads(&self) {
+        self.imp()
+            .picture_loads
+            .take()
+            .into_iter()
+            .for_each(|picture_load| picture_load.abort());
+    }
+}
@@ -0,0 +1,592 @@
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gettextrs::{gettext, ngettext};
+use gtk::glib::subclass::Signal;
+use gtk::{gio, glib};
+use std::cell::{Cell, RefCell};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use url::Url;
+use uuid::Uuid;
+
+use crate::calendar_export::export_calendar;
+use crate::core::category::Category;
+use crate::core::event_details::{EventDetails, ParticipantStats};
+use crate::core::icalendar::CalendarEvent;
+use crate::core::language::Language;
+use crate::core::participation::{JoinOptions, ParticipantRole, Participation};
+use crate::description_view::MobilisationDescriptionView;
+use crate::event_preview::show_status_badge;
+use crate::infra::cache::{Cache, Loaded};
+use crate::infra::client::MobilizonApi;
+use crate::presentation::dates::event_dates_label;
+use crate::presentation::labels::participant_role_label;
+use crate::{runtime, settings};
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[template(resource = "/space/soulie/mobilisation/event_page.ui")]
+    pub struct MobilisationEventPage {
+        #[template_child]
+        pub stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub error: TemplateChild<adw::StatusPage>,
+        #[template_child]
+        pub retry_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub open_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub calendar_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub status_badge: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub date_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub participation_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub role_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub join_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub external_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub leave_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub participation_spinner: TemplateChild<adw::Spinner>,
+        #[template_child]
+        pub participation_error: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub organizer_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub place_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub online_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub participants_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub category_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub language_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub tags: TemplateChild<gtk::FlowBox>,
+        #[template_child]
+        pub description_view: TemplateChild<MobilisationDescriptionView>,
+        pub api: RefCell<Option<Arc<dyn MobilizonApi>>>,
+        /// Where the event is shown from when the instance cannot be reached
+        pub cache: RefCell<Option<Cache>>,
+        pub uuid: Cell<Uuid>,
+        /// Used to show the label of the category rather than its id
+        pub categories: RefCell<Vec<Category>>,
+        pub event_url: RefCell<Option<Url>>,
+        /// The event as exported to calendars, once loaded
+        pub calendar_event: RefCell<Option<CalendarEvent>>,
+        pub online_url: RefCell<Option<Url>>,
+        /// Id of the event in the API, needed to join it
+        pub event_id: RefCell<String>,
+        pub join_options: RefCell<JoinOptions>,
+        pub participant_stats: Cell<ParticipantStats>,
+        pub maximum_attendee_capacity: Cell<Option<i64>>,
+        /// `None` until known, which needs the user to be logged in
+        pub participation: RefCell<Option<Participation>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MobilisationEventPage {
+        const NAME: &'static str = "MobilisationEventPage";
+        type Type = super::MobilisationEventPage;
+        type ParentType = adw::NavigationPage;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MobilisationEventPage {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("role-changed")
+                        .param_types([String::static_type()])
+                        .build(),
+                ]
+            })
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+            self.retry_button.connect_clicked(glib::clone!(
+                #[weak]
+                obj,
+                move |_| obj.load()
+            ));
+            self.open_button.connect_clicked(glib::clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.open_url(obj.imp().event_url.borrow().clone());
+                }
+            ));
+            self.calendar_button.connect_clicked(glib::clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    if let Some(event) = obj.imp().calendar_event.borrow().clone() {
+                        let title = event.summary.clone();
+                        export_calendar(&obj, &title, vec![event]);
+                    }
+                }
+            ));
+            self.online_row.connect_activated(glib::clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.open_url(obj.imp().online_url.borrow().clone());
+                }
+            ));
+            self.join_button.connect_clicked(glib::clone!(
+                #[weak]
+                obj,
+                move |_| obj.join()
+            ));
+            self.leave_button.connect_clicked(glib::clone!(
+                #[weak]
+                obj,
+                move |_| obj.leave()
+            ));
+            self.external_button.connect_clicked(glib::clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    let url = match &*obj.imp().join_options.borrow() {
+                        JoinOptions::External(url) => url.clone(),
+                        _ => None,
+                    };
+                    obj.open_url(url);
+                }
+            ));
+        }
+    }
+    impl WidgetImpl for MobilisationEventPage {}
+    impl NavigationPageImpl for MobilisationEventPage {}
+}
+
+glib::wrapper! {
+    pub struct MobilisationEventPage(ObjectSubclass<imp::MobilisationEventPage>)
+    @extends adw::NavigationPage, gtk::Widget,
+    @implements gtk::Buildable;
+}
+
+impl MobilisationEventPage {
+    pub fn new(
+        api: Arc<dyn MobilizonApi>,
+        cache: Option<Cache>,
+        instance_url: &Url,
+        uuid: Uuid,
+        title: &str,
+        categories: Vec<Category>,
+    ) -> Self {
+        let page = glib::Object::builder::<MobilisationEventPage>()
+            .property("title", title)
+            .build();
+        page.imp()
+            .description_view
+            .set_api(Some(api.clone()), Some(instance_url.clone()));
+        page.imp().api.replace(Some(api));
+        page.imp().cache.replace(cache);
+        page.imp().uuid.set(uuid);
+        page.imp().categories.replace(categories);
+        page.load();
+        page
+    }
+
+    /// Called with the role of the user whenever they join or leave the
+    /// event, including when the server turns it down and the role goes back.
+    pub fn connect_role_changed<F: Fn(&Self, Option<ParticipantRole>) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "role-changed",
+            false,
+            glib::closure_local!(move |page: &Self, role: String| {
+                f(page, ParticipantRole::from_str(&role).ok())
+            }),
+        )
+    }
+
+    /// Shows the event as saved the last time it was opened, if it was,
+    /// until the instance answers.
+    pub fn load(&self) {
+        let Some(api) = self.imp().api.borrow().clone() else {
+            return;
+        };
+        let cache = self.imp().cache.borrow().clone();
+        let uuid = self.imp().uuid.get();
+        self.imp().stack.set_visible_child_name("loading");
+        let (sender, receiver) = async_channel::unbounded();
+        runtime().spawn(async move {
+            if let Some(cache) = &cache {
+                match cache.load_details(uuid).await {
+                    Ok(Some(cached)) => {
+                        let _ = sender.send(Loaded::Cached(cached)).await;
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        glib::g_log!(
+                            glib::LogLevel::Warning,
+                            "Error reading cached event : {}",
+                            err
+                        );
+                    }
+                }
+            }
+            let fetched = api.fetch_event_details(uuid).await;
+            if let (Some(cache), Ok(details)) = (&cache, &fetched)
+                && let Err(err) = cache.save_details(details).await
+            {
+                glib::g_log!(glib::LogLevel::Warning, "Error caching event : {}", err);
+            }
+            let _ = sender.send(Loaded::Fetched(fetched)).await;
+        });
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let mut shown_cached = false;
+                while let Ok(loaded) = receiver.recv().await {
+                    match loaded {
+                        Loaded::Cached(cached) => {
+                            obj.show_details(&cached.value);
+                            shown_cached = true;
+                        }
+                        Loaded::Fetched(Ok(details)) => obj.show_details(&details),
+                        Loaded::Fetched(Err(err)) => {
+                            glib::g_log!(glib::LogLevel::Warning, "Error fetching event : {}", err);
+                            // What was saved is better than nothing
+                            if !shown_cached {
+                                obj.imp().error.set_description(Some(&err.to_string()));
+                                obj.imp().stack.set_visible_child_name("error");
+                            }
+                        }
+                    }
+                }
+            }
+        ));
+    }
+
+    fn show_details(&self, details: &EventDetails) {
+        let imp = self.imp();
+        let event = &details.event;
+        self.set_title(&event.title);
+        imp.title_label.set_label(&event.title);
+        imp.date_label.set_label(&event_dates_label(event));
+        show_status_badge(&imp.status_badge, event.status);
+
+        Self::show_row(
+            &imp.organizer_row,
+            details.organizer.as_ref().map(|actor| actor.display_name()),
+        );
+        Self::show_row(
+            &imp.place_row,
+            details.physical_address.as_ref().map(|address| {
+                match address.description.as_ref().filter(|d| !d.is_empty()) {
+                    Some(place) => format!("{}\n{}", place, address.to_single_line()),
+                    None => address.to_single_line(),
+                }
+            }),
+        );
+        Self::show_row(
+            &imp.online_row,
+            details.online_address.as_ref().map(|url| url.to_string()),
+        );
+        imp.online_url.replace(details.online_address.clone());
+        imp.participant_stats.set(details.participant_stats);
+        imp.maximum_attendee_capacity
+            .set(details.maximum_attendee_capacity);
+        self.show_participants();
+        Self::show_row(
+            &imp.category_row,
+            details.category.as_ref().map(|id| {
+                imp.categories
+                    .borrow()
+                    .iter()
+                    .find(|category| &category.id == id)
+                    .map(|category| category.label.clone())
+                    .unwrap_or_else(|| id.clone())
+            }),
+        );
+        Self::show_row(
+            &imp.language_row,
+            details
+                .language
+                .as_ref()
+                .map(|code| Language::new(code).name()),
+        );
+
+        imp.tags.remove_all();
+        details.tags.iter().for_each(|tag| {
+            let label = gtk::Label::new(Some(&format!("#{}", tag)));
+            label.add_css_class("dim-label");
+            imp.tags.append(&label);
+        });
+        imp.tags.set_visible(!details.tags.is_empty());
+        imp.description_view
+            .set_html(event.description.as_deref().unwrap_or_default());
+        imp.description_view
+            .set_visible(event.description.is_some());
+
+        imp.event_url.replace(details.url.clone());
+        imp.open_button.set_visible(details.url.is_some());
+        imp.calendar_event
+            .replace(Some(CalendarEvent::from(details)));
+        imp.calendar_button.set_visible(true);
+        imp.join_options.replace(details.join_options.clone());
+        // Fetched details follow cached ones, which must not undo joining or
+        // leaving in the meantime
+        let same_event = imp.event_id.replace(details.id.clone()) == details.id;
+        if !same_event {
+            imp.participation.replace(None);
+            imp.participation_error.set_visible(false);
+        }
+        self.update_participation();
+        if !same_event {
+            self.load_participation();
+        }
+        imp.stack.set_visible_child_name("content");
+    }
+
+    fn show_participants(&self) {
+        let imp = self.imp();
+        let going = imp.participant_stats.get().going;
+        let participants = ngettext("{} person going", "{} people going", going as u32)
+            .replace("{}", &going.to_string());
+        Self::show_row(
+            &imp.participants_row,
+            Some(match imp.maximum_attendee_capacity.get() {
+                Some(capacity) => gettext("{going}, {capacity} places in total")
+                    .replace("{going}", &participants)
+                    .replace("{capacity}", &capacity.to_string()),
+                None => participants,
+            }),
+        );
+    }
+
+    /// Participations are per account, so there is nothing to show when
+    /// logged out.
+    fn load_participation(&self) {
+        let logged_in = !settings().string("account-email").is_empty();
+        let Some(api) = self.imp().api.borrow().clone().filter(|_| logged_in) else {
+            return;
+        };
+        let event_id = self.imp().event_id.borrow().clone();
+        let (sender, receiver) = async_channel::unbounded();
+        runtime().spawn(async move {
+            let _ = sender.send(api.fetch_participation(&event_id).await).await;
+        });
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let _ = receiver
+                    .recv()
+                    .await
+                    .map(|value| match value {
+                        Ok(participation) => {
+                            obj.imp().participation.replace(Some(participation));
+                            obj.update_participation();
+                        }
+                        Err(err) => {
+                            glib::g_log!(
+                                glib::LogLevel::Warning,
+                                "Error fetching participation : {}",
+                                err
+                            );
+                        }
+                    })
+                    .map_err(|err| {
+                        glib::g_log!(glib::LogLevel::Error, "Channel error : {}", err);
+                    });
+            }
+        ));
+    }
+
+    /// Shows what the user can do about the event given how it is joined and
+    /// where they stand.
+    fn update_participation(&self) {
+        let imp = self.imp();
+        let join_options = imp.join_options.borrow();
+        let participation = imp.participation.borrow();
+        let role = participation.as_ref().and_then(|p| p.role);
+        let external_url = match &*join_options {
+            JoinOptions::External(url) => url.as_ref(),
+            _ => None,
+        };
+        imp.external_button.set_visible(external_url.is_some());
+        imp.join_button.set_visible(
+            participation.is_some() && role.is_none() && join_options.expected_role().is_some(),
+        );
+        imp.join_button.set_label(&match *join_options {
+            JoinOptions::Restricted => gettext("_Ask to Join"),
+            _ => gettext("_Join"),
+        });
+        imp.leave_button
+            .set_visible(role.is_some_and(|role| role.can_leave()));
+        imp.leave_button.set_label(&match role {
+            Some(ParticipantRole::NotApproved) => gettext("_Cancel Request"),
+            _ => gettext("_Leave"),
+        });
+        imp.role_label.set_visible(role.is_some());
+        if let Some(role) = role {
+            imp.role_label.set_label(&participant_role_label(&role));
+        }
+        imp.participation_box
+            .set_visible(participation.is_some() || external_url.is_some());
+    }
+
+    fn set_participation_busy(&self, busy: bool) {
+        let imp = self.imp();
+        imp.participation_spinner.set_visible(busy);
+        imp.join_button.set_sensitive(!busy);
+        imp.leave_button.set_sensitive(!busy);
+    }
+
+    /// Shows the user in their new role straight away, and goes back to how
+    /// things were if the server does not agree.
+    fn change_role(&self, role: Option<ParticipantRole>) -> Option<ParticipantRole> {
+        let imp = self.imp();
+        let mut participation = imp.participation.borrow_mut();
+        let previous = participation.as_ref().and_then(|p| p.role);
+        if let Some(participation) = participation.as_mut() {
+            participation.role = role;
+        }
+        drop(participation);
+        imp.participant_stats
+            .set(imp.participant_stats.get().with_role_change(previous, role));
+        self.show_participants();
+        self.update_participation();
+        self.emit_by_name::<()>(
+            "role-changed",
+            &[&role.map(|role| role.as_str()).unwrap_or_default()],
+        );
+        previous
+    }
+
+    fn join(&self) {
+        let imp = self.imp();
+        let (Some(api), Some(participation)) =
+            (imp.api.borrow().clone(), imp.participation.borrow().clone())
+        else {
+            return;
+        };
+        let event_id = imp.event_id.borrow().clone();
+        let expected_role = imp.join_options.borrow().expected_role();
+        let previous_role = self.change_role(expected_role);
+        imp.participation_error.set_visible(false);
+        self.set_participation_busy(true);
+        let (sender, receiver) = async_channel::unbounded();
+        runtime().spawn(async move {
+            let _ = sender
+                .send(api.join_event(&event_id, &participation.actor_id).await)
+                .await;
+        });
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let received = receiver.recv().await;
+                obj.set_participation_busy(false);
+                let _ = received
+                    .map(|value| match value {
+                        // The server knows better, e.g. when the event
+                        // became restricted in the meantime
+                        Ok(role) => {
+                            obj.change_role(role.or(expected_role));
+                        }
+                        Err(err) => {
+                            glib::g_log!(glib::LogLevel::Warning, "Error joining event : {}", err);
+                            obj.change_role(previous_role);
+                            obj.show_participation_error(&err.to_string());
+                        }
+                    })
+                    .map_err(|err| {
+                        glib::g_log!(glib::LogLevel::Error, "Channel error : {}", err);
+                        obj.change_role(previous_role);
+                    });
+            }
+        ));
+    }
+
+    fn leave(&self) {
+        let imp = self.imp();
+        let (Some(api), Some(participation)) =
+            (imp.api.borrow().clone(), imp.participation.borrow().clone())
+        else {
+            return;
+        };
+        let event_id = imp.event_id.borrow().clone();
+        let previous_role = self.change_role(None);
+        imp.participation_error.set_visible(false);
+        self.set_participation_busy(true);
+        let (sender, receiver) = async_channel::unbounded();
+        runtime().spawn(async move {
+            let _ = sender
+                .send(api.leave_event(&event_id, &participation.actor_id).await)
+                .await;
+        });
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let received = receiver.recv().await;
+                obj.set_participation_busy(false);
+                let _ = received
+                    .map(|value| {
+                        if let Err(err) = value {
+                            glib::g_log!(glib::LogLevel::Warning, "Error leaving event : {}", err);
+                            obj.change_role(previous_role);
+                            obj.show_participation_error(&err.to_string());
+                        }
+                    })
+                    .map_err(|err| {
+                        glib::g_log!(glib::LogLevel::Error, "Channel error : {}", err);
+                        obj.change_role(previous_role);
+                    });
+            }
+        ));
+    }
+
+    fn show_participation_error(&self, message: &str) {
+        self.imp().participation_error.set_label(message);
+        self.imp().participation_error.set_visible(true);
+    }
+
+    fn show_row(row: &adw::ActionRow, value: Option<String>) {
+        row.set_visible(value.is_some());
+        row.set_subtitle(value.as_deref().unwrap_or_default());
+    }
+
+    fn open_url(&self, url: Option<Url>) {
+        let Some(url) = url else {
+            return;
+        };
+        gtk::UriLauncher::new(url.as_str()).launch(
+            self.root().and_downcast_ref::<gtk::Window>(),
+            gio::Cancellable::NONE,
+            |result| {
+                if let Err(err) = result {
+                    glib::g_log!(glib::LogLevel::Warning, "Error opening url : {}", err);
+                }
+            },
+        );
+    }
+}
@@ -1,12 +1,33 @@
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use core::cell;
-use gtk::gdk;
-use gtk::glib;
+use gettextrs::gettext;
+use gtk::{gdk, gio, glib};
+use std::sync::Arc;
 use url::Url;
 
-use crate::infra::events::fetch_event_picture;
-use crate::{http_client, runtime};
+use crate::clock::{self, ClockWatch};
+use crate::core::event::{Event, EventStatus, HumanReadableDateTime};
+use crate::core::lru::Lru;
+use crate::core::participation::ParticipantRole;
+use crate::infra::client::MobilizonApi;
+use crate::pictures::{PictureFit, decode_picture};
+use crate::presentation::dates::human_readable_label;
+use crate::presentation::labels::{event_status_label, participant_role_badge};
+use crate::runtime;
+
+/// Size of the picture of an event on its card, in logical pixels.
+const CARD_PICTURE_SIZE: i32 = 96;
+
+/// Decoded pictures kept around, enough for a few screens of events.
+const TEXTURE_CACHE_CAPACITY: usize = 100;
+
+thread_local! {
+    /// Textures are not `Send` so they stay on the main thread, where list
+    /// items are bound.
+    static TEXTURES: cell::RefCell<Lru<Url, gdk::Texture>> =
+        cell::RefCell::new(Lru::new(TEXTURE_CACHE_CAPACITY));
+}
 
 mod imp {
     use super::*;
@@ -20,13 +41,30 @@ mod imp {
         #[template_child]
         pub event_description: TemplateChild<gtk::Label>,
         #[template_child]
+        pub excerpt: TemplateChild<gtk::Label>,
+        #[template_child]
         pub time: TemplateChild<gtk::Label>,
         #[template_child]
+        pub status_badge: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub role_badge: TemplateChild<gtk::Label>,
+        #[template_child]
         pub picture: TemplateChild<gtk::Picture>,
         #[template_child]
         pub spinner: TemplateChild<adw::Spinner>,
+        /// Shown instead of the picture for events that do not have one
+        #[template_child]
+        pub placeholder: TemplateChild<adw::Avatar>,
         #[property(get, set)]
         pub picture_url: cell::RefCell<Option<String>>,
+        /// Where the picture is downloaded from
+        pub api: cell::RefCell<Option<Arc<dyn MobilizonApi>>>,
+        pub picture_load: cell::RefCell<Option<tokio::task::AbortHandle>>,
+        /// Bumped every time the picture changes so that a picture requested
+        /// before the widget was recycled is dropped when it arrives.
+        pub picture_generation: cell::Cell<u64>,
+        /// Updates the time shown as long as the event is
+        pub time_watch: cell::RefCell<Option<ClockWatch>>,
     }
 
     #[glib::object_subclass]
@@ -50,6 +88,19 @@ mod imp {
     impl BoxImpl for MobilisationEventPreview {}
 }
 
+/// Only statuses that change what the reader should expect get a badge.
+pub fn show_status_badge(badge: &gtk::Label, status: EventStatus) {
+    badge.remove_css_class("warning");
+    badge.remove_css_class("error");
+    match status {
+        EventStatus::Confirmed => (),
+        EventStatus::Tentative => badge.add_css_class("warning"),
+        EventStatus::Cancelled => badge.add_css_class("error"),
+    }
+    badge.set_label(&event_status_label(&status));
+    badge.set_visible(status != EventStatus::Confirmed);
+}
+
 glib::wrapper! {
     pub struct MobilisationEventPreview(ObjectSubclass<imp::MobilisationEventPreview>)
     @extends gtk::Box, gtk::Widget,
@@ -57,40 +108,229 @@ glib::wrapper! {
 }
 
 impl MobilisationEventPreview {
+    pub fn set_api(&self, api: Option<Arc<dyn MobilizonApi>>) {
+        self.imp().api.replace(api);
+    }
+
+    pub fn set_status(&self, status: EventStatus) {
+        show_status_badge(&self.imp().status_badge, status);
+    }
+
+    /// Shows the beginning of the description of the event, on two lines
+    /// at most, when there is one.
+    pub fn set_excerpt(&self, excerpt: &str) {
+        let label = &self.imp().excerpt;
+        label.set_label(excerpt);
+        label.set_visible(!excerpt.is_empty());
+    }
+
+    /// Shows when `event` takes place, keeping it up to date as time passes
+    /// until `unwatch_time` is called.
+    pub fn watch_time(&self, event: Event) {
+        let now = chrono::Local::now();
+        self.set_time(&event.compute_human_readable_begining(Some(&move || now)));
+        let watch = event.next_human_readable_change(now).map(|due| {
+            clock::watch(
+                due,
+                glib::clone!(
+                    #[weak(rename_to = preview)]
+                    self,
+                    #[upgrade_or]
+                    None,
+                    move |now| {
+                        preview
+                            .set_time(&event.compute_human_readable_begining(Some(&move || now)));
+                        event.next_human_readable_change(now)
+                    }
+                ),
+            )
+        });
+        self.imp().time_watch.replace(watch);
+    }
+
+    /// Stops updating the time, for list items no longer shown.
+    pub fn unwatch_time(&self) {
+        self.imp().time_watch.take();
+    }
+
+    /// Shows when the event takes place, highlighting events about to begin
+    /// or happening, and dimming those that are over.
+    pub fn set_time(&self, date_time: &HumanReadableDateTime) {
+        let time = &self.imp().time;
+        time.remove_css_class("accent");
+        time.remove_css_class("dim-label");
+        match date_time {
+            HumanReadableDateTime::Ended => time.add_css_class("dim-label"),
+            HumanReadableDateTime::Ongoing { .. }
+            | HumanReadableDateTime::OngoingDay { .. }
+            | HumanReadableDateTime::StartingIn { .. } => time.add_css_class("accent"),
+            HumanReadableDateTime::Tomorrow(_) | HumanReadableDateTime::Later(..) => (),
+        }
+        time.set_label(&human_readable_label(date_time));
+    }
+
+    /// Shows the role of the user in the event, only set on their own events.
+    pub fn set_role(&self, role: Option<ParticipantRole>) {
+        let badge = &self.imp().role_badge;
+        ["accent", "success", "warning", "error"]
+            .iter()
+            .for_each(|class| badge.remove_css_class(class));
+        badge.set_visible(role.is_some());
+        let Some(role) = role else {
+            return;
+        };
+        badge.add_css_class(match role {
+            ParticipantRole::NotApproved | ParticipantRole::NotConfirmed => "warning",
+            ParticipantRole::Rejected => "error",
+            ParticipantRole::Participant => "success",
+            _ => "accent",
+        });
+        badge.set_label(&participant_role_badge(&role));
+    }
+
+    /// Empties the tile, for list items about to show another event.
+    pub fn clear_picture(&self) {
+        let imp = self.imp();
+        self.cancel_picture_load();
+        imp.picture.set_paintable(None::<&gdk::Paintable>);
+        imp.picture.set_visible(false);
+        imp.spinner.set_visible(false);
+        imp.placeholder.set_visible(false);
+    }
+
+    /// Tells that the event has a picture, which could not be loaded.
+    pub fn show_broken_picture(&self) {
+        let imp = self.imp();
+        self.cancel_picture_load();
+        imp.placeholder.set_show_initials(false);
+        imp.placeholder
+            .set_icon_name(Some("image-missing-symbolic"));
+        imp.placeholder
+            .set_tooltip_text(Some(&gettext("The picture could not be loaded")));
+        imp.placeholder.set_visible(true);
+        imp.picture.set_visible(false);
+        imp.spinner.set_visible(false);
+    }
+
+    /// Stops waiting for the picture being loaded, returning the generation
+    /// of the next one.
+    fn cancel_picture_load(&self) -> u64 {
+        let imp = self.imp();
+        if let Some(picture_load) = imp.picture_load.take() {
+            picture_load.abort();
+        }
+        let generation = imp.picture_generation.get() + 1;
+        imp.picture_generation.set(generation);
+        generation
+    }
+
+    /// Shows a tile with the initials of the title in place of the picture.
+    pub fn show_placeholder(&self, title: &str) {
+        let imp = self.imp();
+        self.cancel_picture_load();
+        imp.placeholder.set_text(Some(title));
+        imp.placeholder.set_show_initials(true);
+        imp.placeholder.set_tooltip_text(None);
+        imp.placeholder.set_visible(true);
+        imp.picture.set_visible(false);
+        imp.spinner.set_visible(false);
+    }
+
     pub fn new() -> Self {
         let a = glib::Object::builder::<MobilisationEventPreview>().build();
-                                    println!("object built");
         a.connect_picture_url_notify(|e| {
-                                    println!("picture property modified");
             let x = e.imp();
             match x.picture_url.take() {
-                None => return,
+                None => (),
                 Some(picture_url) => {
+                    let Some(api) = x.api.borrow().clone() else {
+                        return;
+                    };
+                    let parsed_picture_url = match Url::parse(picture_url.as_str()) {
+                        Ok(url) => url,
+                        Err(err) => {
+                            glib::g_log!(
+                                glib::LogLevel::Warning,
+                                "Error parsing picture url : {}",
+                                err
+                            );
+                            e.show_broken_picture();
+                            return;
+                        }
+                    };
+                    let generation = e.cancel_picture_load();
+                    x.placeholder.set_visible(false);
+                    x.spinner.set_visible(false);
+                    if let Some(texture) =
+                        TEXTURES.with_borrow_mut(|textures| textures.get(&parsed_picture_url))
+                    {
+                        x.picture.set_paintable(Some(&texture));
+                        x.picture.set_visible(true);
+                        return;
+                    }
+                    x.picture.set_paintable(None::<&gdk::Paintable>);
+                    x.picture.set_visible(false);
+                    x.spinner.set_visible(true);
                     let (sender, receiver) = async_channel::unbounded();
-                    let parsed_picture_url = Url::parse(picture_url.as_str())
-                        .expect("Failed to parse a normally parsable url.");
-                    runtime().spawn(async move {
-                                    println!("fetching");
+                    let texture_url = parsed_picture_url.clone();
+                    let picture_load = runtime().spawn(async move {
                         let _ = sender
-                            .send(fetch_event_picture(http_client(), &parsed_picture_url).await)
+                            .send(api.fetch_event_picture(&parsed_picture_url).await)
                             .await;
                     });
+                    x.picture_load.replace(Some(picture_load.abort_handle()));
+                    let size = CARD_PICTURE_SIZE * e.scale_factor();
                     glib::spawn_future_local(glib::clone!(
                         #[weak(rename_to=obj)]
                         x,
                         async move {
-                            let _ = receiver.recv().await.map(|v| {
-                                println!("answer received");
-                                let _ = v.map(move |bytes| {
-                                    let bytes = glib::Bytes::from(bytes.as_ref());
-                                    let texture = gdk::Texture::from_bytes(&bytes).unwrap();
+                            let received = receiver.recv().await;
+                            if obj.picture_generation.get() != generation {
+                                return;
+                            }
+                            obj.picture_load.take();
+                            let bytes = match received {
+                                Ok(Ok(bytes)) => glib::Bytes::from_owned(bytes),
+                                Ok(Err(err)) => {
+                                    glib::g_log!(
+                                        glib::LogLevel::Warning,
+                                        "Error fetching picture : {}",
+                                        err
+                                    );
+                                    obj.obj().show_broken_picture();
+                                    return;
+                                }
+                                // The load was cancelled
+                                Err(_) => return,
+                            };
+                            let decoded = gio::spawn_blocking(move || {
+                                decode_picture(&bytes, PictureFit::Cover(size))
+                            })
+                            .await;
+                            if obj.picture_generation.get() != generation {
+                                return;
+                            }
+                            match decoded {
+                                Ok(Ok(texture)) => {
+                                    TEXTURES.with_borrow_mut(|textures| {
+                                        textures.insert(texture_url, texture.clone())
+                                    });
                                     obj.picture.set_paintable(Some(&texture));
                                     obj.picture.set_visible(true);
                                     obj.spinner.set_visible(false);
-                                });
-                            });
+                                }
+                                Ok(Err(err)) => {
+                                    glib::g_log!(
+                                        glib::LogLevel::Warning,
+                                        "Error decoding picture : {}",
+                                        err
+                                    );
+                                    obj.obj().show_broken_picture();
+                                }
+                                Err(_) => obj.obj().show_broken_picture(),
+                            }
                         }
-                    ))
+                    ));
                 }
             };
         });
@@ -1,9 +1,13 @@
 use adw::prelude::*;
 use core::cell;
-use gtk::glib::{self, GString, Object};
+use gtk::glib::{self, Object};
 use gtk::subclass::prelude::*;
 
-use crate::core::event::{DateTime, Event, HumanReadableDateTime};
+use crate::core::event::{Event, EventStatus, TimelineSection};
+use crate::core::participation::{EventParticipation, ParticipantRole};
+use crate::presentation::dates::event_dates_label;
+use crate::presentation::html::excerpt;
+use std::str::FromStr;
 
 mod imp {
     use super::*;
@@ -11,14 +15,28 @@ mod imp {
     #[derive(glib::Properties, Default)]
     #[properties(wrapper_type = super::MobilisationEventPreviewModel)]
     pub struct MobilisationEventPreviewModel {
+        #[property(get, set)]
+        pub uuid: cell::RefCell<String>,
         #[property(get, set)]
         pub title: cell::RefCell<String>,
         #[property(get, set)]
         pub picture_url: cell::RefCell<Option<String>>,
         #[property(get, set)]
-        pub human_readable_time: cell::RefCell<String>,
-        #[property(get, set)]
         pub description: cell::RefCell<String>,
+        /// Beginning of the description of the event, empty when unknown
+        #[property(get, set)]
+        pub excerpt: cell::RefCell<String>,
+        /// Name of the `EventStatus` in the API
+        #[property(get, set)]
+        pub status: cell::RefCell<String>,
+        /// Name of the `ParticipantRole` of the user in the API, empty on the
+        /// timeline until they join the event
+        #[property(get, set)]
+        pub role: cell::RefCell<String>,
+        /// The event the properties are taken from
+        pub event: cell::RefCell<Event>,
+        /// Group of the timeline the event was last put in
+        pub section: cell::Cell<TimelineSection>,
     }
 
     #[glib::object_subclass]
@@ -38,48 +56,69 @@ glib::wrapper! {
 
 impl MobilisationEventPreviewModel {
     pub fn new(event: &Event) -> Self {
-        Object::builder()
+        let model: Self = Object::builder()
+            .property("uuid", event.id.to_string())
             .property("title", &event.title)
             .property(
                 "picture_url",
                 event.picture_url.as_ref().map(|e| e.to_string()),
             )
-            .property("description", Self::make_date_string(event))
+            .property("description", event_dates_label(event))
             .property(
-                "human_readable_time",
-                match event.compute_human_readable_begining(
-                    None::<&fn() -> chrono::DateTime<chrono::Local>>,
-                ) {
-                    HumanReadableDateTime::Later(date) => date,
-                    HumanReadableDateTime::Now => "Now".to_string(),
-                },
+                "excerpt",
+                event
+                    .description
+                    .as_deref()
+                    .map(excerpt)
+                    .unwrap_or_default(),
             )
-            .build()
+            .property("status", event.status.as_str())
+            .build();
+        model.imp().event.replace(event.clone());
+        model
+            .imp()
+            .section
+            .set(event.timeline_section(chrono::Local::now()));
+        model
     }
 
-    fn make_date_string(event: &Event) -> String {
-        if event.is_long() {
-            format!(
-                "{} to {}",
-                Self::localize_date(&event.begins_on),
-                Self::localize_date(&event.ends_on)
-            )
-        } else {
-            format!(
-                "{} - {}h",
-                Self::localize_date(&event.begins_on).to_string(),
-                event.compute_duration_in_hours()
-            )
-        }
+    pub fn timeline_section(&self) -> TimelineSection {
+        self.imp().section.get()
+    }
+
+    /// Puts the event in the group of the timeline it falls in at `now`,
+    /// telling whether it moved.
+    pub fn update_timeline_section(&self, now: chrono::DateTime<chrono::Local>) -> bool {
+        let section = self.imp().event.borrow().timeline_section(now);
+        self.imp().section.replace(section) != section
+    }
+
+    pub fn next_section_change(
+        &self,
+        now: chrono::DateTime<chrono::Local>,
+    ) -> Option<chrono::DateTime<chrono::Local>> {
+        self.imp().event.borrow().next_section_change(now)
+    }
+
+    pub fn event(&self) -> Event {
+        self.imp().event.borrow().clone()
+    }
+
+    pub fn with_role(participation: &EventParticipation) -> Self {
+        let model = Self::new(&participation.event);
+        model.set_role(participation.role.as_str());
+        model
+    }
+
+    pub fn set_participant_role(&self, role: Option<ParticipantRole>) {
+        self.set_role(role.map(|role| role.as_str()).unwrap_or_default());
+    }
+
+    pub fn participant_role(&self) -> Option<ParticipantRole> {
+        ParticipantRole::from_str(&self.role()).ok()
     }
 
-    fn localize_date(date_time: &DateTime) -> GString {
-        /* We unwrap here because cases where a chrono::DateTime outputs an
-         * rfc3339 compliant string and glib::DateTime::from_iso8601 fails to
-         * read it seem improbable enough that we choose not to care for this
-         * prototype */
-        glib::DateTime::from_iso8601(&date_time.to_rfc3339(), None)
-            .and_then(|v| v.format("%x %X"))
-            .unwrap()
+    pub fn event_status(&self) -> EventStatus {
+        EventStatus::from_str(&self.status()).unwrap_or_default()
     }
 }
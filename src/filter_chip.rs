@@ -0,0 +1,55 @@
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::glib;
+
+use crate::sidebar_row::MobilisationSidebarRow;
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[template(resource = "/space/soulie/mobilisation/filter_chip.ui")]
+    pub struct MobilisationFilterChip {
+        #[template_child]
+        pub chip_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MobilisationFilterChip {
+        const NAME: &'static str = "MobilisationFilterChip";
+        type Type = super::MobilisationFilterChip;
+        type ParentType = gtk::Button;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MobilisationFilterChip {}
+    impl WidgetImpl for MobilisationFilterChip {}
+    impl ButtonImpl for MobilisationFilterChip {}
+}
+
+glib::wrapper! {
+    pub struct MobilisationFilterChip(ObjectSubclass<imp::MobilisationFilterChip>)
+    @extends gtk::Button, gtk::Widget,
+    @implements gtk::Buildable, gtk::Actionable;
+}
+
+impl MobilisationFilterChip {
+    /// A chip standing for an active sidebar row, turning it off when clicked.
+    pub fn new(row: &MobilisationSidebarRow) -> Self {
+        let chip = glib::Object::builder::<MobilisationFilterChip>().build();
+        chip.imp().chip_label.set_label(&row.label());
+        chip.connect_clicked(glib::clone!(
+            #[weak]
+            row,
+            move |_| row.set_active(false)
+        ));
+        chip
+    }
+}
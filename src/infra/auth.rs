@@ -0,0 +1,219 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{TimeDelta, Utc};
+use graphql_client::{GraphQLQuery, Response};
+use reqwest;
+use serde::Deserialize;
+
+use crate::infra::client::MobilizonClient;
+use crate::infra::graphql::{GraphQLErrors, check_errors};
+
+type DateTime = chrono::DateTime<Utc>;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.json",
+    query_path = "graphql/auth.graphql",
+    response_derives = "Debug"
+)]
+struct LoginMutation;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.json",
+    query_path = "graphql/auth.graphql",
+    response_derives = "Debug"
+)]
+struct RefreshTokenMutation;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.json",
+    query_path = "graphql/auth.graphql",
+    response_derives = "Debug"
+)]
+struct LogoutMutation;
+
+/// Errors when logging in or out, or when keeping the session alive
+pub enum AuthError {
+    HttpError(Box<reqwest::Error>),
+    MissingField(String),
+    /// Also what a wrong password or an expired session looks like
+    GraphQLErrors(GraphQLErrors),
+}
+
+/// Access tokens are refreshed this long before they expire, so that they
+/// do not expire on their way to the server.
+const REFRESH_MARGIN: TimeDelta = TimeDelta::seconds(30);
+
+/// How long access tokens with no readable expiry are used before being
+/// refreshed, shorter than what Mobilizon gives them.
+const ASSUMED_LIFETIME: TimeDelta = TimeDelta::minutes(10);
+
+/// Tokens of a logged in user
+#[derive(Clone)]
+pub struct Session {
+    /// `None` until the session resumed from a refresh token gets refreshed
+    pub access_token: Option<String>,
+    pub expires_at: Option<DateTime>,
+    pub refresh_token: String,
+}
+
+impl Session {
+    fn new(access_token: String, refresh_token: String) -> Self {
+        Self {
+            expires_at: Some(
+                token_expiry(&access_token).unwrap_or_else(|| Utc::now() + ASSUMED_LIFETIME),
+            ),
+            access_token: Some(access_token),
+            refresh_token,
+        }
+    }
+
+    /// A session that only knows its refresh token, as saved between runs.
+    pub fn resume(refresh_token: String) -> Self {
+        Self {
+            access_token: None,
+            expires_at: None,
+            refresh_token,
+        }
+    }
+
+    pub fn needs_refresh(&self, now: DateTime) -> bool {
+        match (&self.access_token, self.expires_at) {
+            (Some(_), Some(expires_at)) => expires_at - REFRESH_MARGIN <= now,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    exp: i64,
+}
+
+/// Reads the expiry out of the access token, which is a JWT.
+fn token_expiry(access_token: &str) -> Option<DateTime> {
+    let payload = access_token.split('.').nth(1)?;
+    let claims = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let claims = serde_json::from_slice::<Claims>(&claims).ok()?;
+    DateTime::from_timestamp(claims.exp, 0)
+}
+
+pub(super) async fn login(
+    client: &MobilizonClient,
+    email: &str,
+    password: &str,
+) -> Result<Session, AuthError> {
+    let response = client
+        .anonymous_graphql_request::<LoginMutation>(login_mutation::Variables {
+            email: email.to_string(),
+            password: password.to_string(),
+        })
+        .send()
+        .await
+        .map_err(|e| AuthError::HttpError(Box::new(e)))?
+        .json::<Response<login_mutation::ResponseData>>()
+        .await
+        .map_err(|e| AuthError::HttpError(Box::new(e)))?;
+    let data =
+        check_errors(response).map_err(|errors| AuthError::GraphQLErrors(errors.without_data()))?;
+    let login = data
+        .and_then(|data| data.login)
+        .ok_or(AuthError::MissingField("login".to_string()))?;
+    Ok(Session::new(login.access_token, login.refresh_token))
+}
+
+pub(super) async fn refresh(
+    client: &MobilizonClient,
+    refresh_token: &str,
+) -> Result<Session, AuthError> {
+    let response = client
+        .anonymous_graphql_request::<RefreshTokenMutation>(refresh_token_mutation::Variables {
+            refresh_token: refresh_token.to_string(),
+        })
+        .send()
+        .await
+        .map_err(|e| AuthError::HttpError(Box::new(e)))?
+        .json::<Response<refresh_token_mutation::ResponseData>>()
+        .await
+        .map_err(|e| AuthError::HttpError(Box::new(e)))?;
+    let data =
+        check_errors(response).map_err(|errors| AuthError::GraphQLErrors(errors.without_data()))?;
+    let refreshed = data
+        .and_then(|data| data.refresh_token)
+        .ok_or(AuthError::MissingField("refreshToken".to_string()))?;
+    Ok(Session::new(
+        refreshed.access_token,
+        refreshed.refresh_token,
+    ))
+}
+
+/// Revokes the session with the access token it has, which is not worth
+/// refreshing since the session ends anyway.
+pub(super) async fn logout(client: &MobilizonClient, session: &Session) -> Result<(), AuthError> {
+    let response = client
+        .send_query(
+            &LogoutMutation::build_query(logout_mutation::Variables {
+                refresh_token: session.refresh_token.clone(),
+            }),
+            session.access_token.as_deref(),
+        )
+        .await
+        .map_err(|e| AuthError::HttpError(Box::new(e)))?
+        .json::<Response<logout_mutation::ResponseData>>()
+        .await
+        .map_err(|e| AuthError::HttpError(Box::new(e)))?;
+    check_errors(response)
+        .map(|_| ())
+        .map_err(|errors| AuthError::GraphQLErrors(errors.without_data()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(claims: &str) -> String {
+        format!(
+            "{}.{}.signature",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"HS512","typ":"JWT"}"#),
+            URL_SAFE_NO_PAD.encode(claims)
+        )
+    }
+
+    #[test]
+    fn expiry_is_read_from_the_token() {
+        assert_eq!(
+            token_expiry(&token(r#"{"exp":1735725600,"sub":"User:1"}"#)),
+            DateTime::from_timestamp(1735725600, 0)
+        );
+    }
+
+    #[test]
+    fn unreadable_token_has_no_expiry() {
+        assert_eq!(token_expiry("not a jwt"), None);
+        assert_eq!(token_expiry(&token(r#"{"sub":"User:1"}"#)), None);
+    }
+
+    #[test]
+    fn session_is_refreshed_just_before_expiry() {
+        let session = Session::new(token(r#"{"exp":1735725600}"#), "refresh".to_string());
+        let expiry = DateTime::from_timestamp(1735725600, 0).unwrap();
+        assert!(!session.needs_refresh(expiry - TimeDelta::minutes(5)));
+        assert!(session.needs_refresh(expiry - TimeDelta::seconds(10)));
+        assert!(session.needs_refresh(expiry + TimeDelta::minutes(5)));
+    }
+
+    #[test]
+    fn session_with_unreadable_expiry_is_refreshed_all_the_same() {
+        let session = Session::new("not a jwt".to_string(), "refresh".to_string());
+        assert!(!session.needs_refresh(Utc::now()));
+        assert!(session.needs_refresh(Utc::now() + TimeDelta::hours(1)));
+    }
+
+    #[test]
+    fn resumed_session_is_refreshed_first() {
+        let session = Session::resume("refresh".to_string());
+        assert!(session.needs_refresh(Utc::now()));
+    }
+}
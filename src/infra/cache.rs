@@ -0,0 +1,581 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use url::Url;
+use uuid::Uuid;
+
+use crate::core::category::Category;
+use crate::core::event::{DateTime, Event, EventStatus};
+use crate::core::event_details::{Actor, Address, EventDetails, ParticipantStats};
+use crate::core::instance_version::InstanceVersion;
+use crate::core::language::Language;
+use crate::core::participation::JoinOptions;
+use crate::infra::config::FetchConfigResponse;
+
+const CONFIG_FILE: &str = "config.json";
+/// Uuids of the events of the timeline, each saved in `EVENTS_DIRECTORY`
+const TIMELINE_FILE: &str = "timeline.json";
+const EVENTS_DIRECTORY: &str = "events";
+const DETAILS_DIRECTORY: &str = "details";
+
+/// Events opened this long ago are forgotten, most of them are over.
+const DETAILS_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+pub enum CacheError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+/// Something read from the cache, along with when it was fetched.
+#[derive(Debug)]
+pub struct Cached<T> {
+    pub value: T,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+/// Content shown at once from the cache, then fetched from the instance.
+pub enum Loaded<T, F> {
+    Cached(Cached<T>),
+    Fetched(F),
+}
+
+/// What was last fetched from an instance, kept in a directory of its own so
+/// that it can be shown before the instance answers, or without network.
+/// Events are saved under their uuid.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    directory: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    updated_at: chrono::DateTime<Utc>,
+    value: T,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ConfigEntry {
+    instance_version: String,
+    categories: Vec<CategoryEntry>,
+    languages: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CategoryEntry {
+    id: String,
+    label: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EventEntry {
+    id: Uuid,
+    title: String,
+    description: Option<String>,
+    picture_url: Option<String>,
+    begins_on: chrono::DateTime<Utc>,
+    ends_on: chrono::DateTime<Utc>,
+    status: String,
+    time_zone: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DetailsEntry {
+    event: EventEntry,
+    id: String,
+    url: Option<String>,
+    category: Option<String>,
+    language: Option<String>,
+    tags: Vec<String>,
+    physical_address: Option<AddressEntry>,
+    online_address: Option<String>,
+    organizer: Option<ActorEntry>,
+    going: i64,
+    not_approved: i64,
+    maximum_attendee_capacity: Option<i64>,
+    /// Name of the join options in the API, e.g. "RESTRICTED"
+    join_options: String,
+    external_participation_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AddressEntry {
+    description: Option<String>,
+    street: Option<String>,
+    postal_code: Option<String>,
+    locality: Option<String>,
+    region: Option<String>,
+    country: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ActorEntry {
+    name: Option<String>,
+    username: String,
+    domain: Option<String>,
+}
+
+impl Cache {
+    /// Cache of the instance at `instance_url`, stored under `base`.
+    pub fn new(base: &Path, instance_url: &Url) -> Self {
+        Cache {
+            directory: base.join(instance_key(instance_url)),
+        }
+    }
+
+    pub async fn load_config(&self) -> Result<Option<Cached<FetchConfigResponse>>, CacheError> {
+        let Some(entry) = self.read::<ConfigEntry>(CONFIG_FILE).await? else {
+            return Ok(None);
+        };
+        // An entry that no longer makes sense is as good as no entry
+        let Ok(instance_version) = InstanceVersion::from_str(&entry.value.instance_version) else {
+            return Ok(None);
+        };
+        Ok(Some(Cached {
+            value: FetchConfigResponse {
+                instance_version,
+                categories: entry
+                    .value
+                    .categories
+                    .into_iter()
+                    .map(|category| Category {
+                        id: category.id,
+                        label: category.label,
+                    })
+                    .collect(),
+                languages: entry
+                    .value
+                    .languages
+                    .iter()
+                    .map(|code| Language::new(code))
+                    .collect(),
+            },
+            updated_at: entry.updated_at,
+        }))
+    }
+
+    pub async fn save_config(&self, config: &FetchConfigResponse) -> Result<(), CacheError> {
+        let value = ConfigEntry {
+            instance_version: config.instance_version.to_string(),
+            categories: config
+                .categories
+                .iter()
+                .map(|category| CategoryEntry {
+                    id: category.id.clone(),
+                    label: category.label.clone(),
+                })
+                .collect(),
+            languages: config
+                .languages
+                .iter()
+                .map(|language| language.code.clone())
+                .collect(),
+        };
+        self.write(CONFIG_FILE, &value).await
+    }
+
+    /// First events of the timeline, unfiltered.
+    pub async fn load_timeline(&self) -> Result<Option<Cached<Vec<Event>>>, CacheError> {
+        let Some(entry) = self.read::<Vec<Uuid>>(TIMELINE_FILE).await? else {
+            return Ok(None);
+        };
+        let mut events = Vec::new();
+        for uuid in entry.value {
+            // Events are read one after the other, an event missing is left out
+            if let Some(event) = self.read::<EventEntry>(&event_file(uuid)).await? {
+                events.extend(decode_event(event.value));
+            }
+        }
+        Ok(Some(Cached {
+            value: events,
+            updated_at: entry.updated_at,
+        }))
+    }
+
+    pub async fn save_timeline(&self, events: &[Event]) -> Result<(), CacheError> {
+        for event in events {
+            self.write(&event_file(event.id), &encode_event(event))
+                .await?;
+        }
+        let uuids = events.iter().map(|event| event.id).collect::<Vec<Uuid>>();
+        self.write(TIMELINE_FILE, &uuids).await?;
+        // Events that left the timeline are no longer needed
+        let kept = uuids
+            .iter()
+            .map(|uuid| format!("{}.json", uuid))
+            .collect::<Vec<String>>();
+        remove_files(&self.directory.join(EVENTS_DIRECTORY), |name, _| {
+            !kept.iter().any(|kept| kept == name)
+        })
+        .await
+        .map_err(CacheError::Io)
+    }
+
+    /// The event as shown on its page the last time it was opened.
+    pub async fn load_details(
+        &self,
+        uuid: Uuid,
+    ) -> Result<Option<Cached<EventDetails>>, CacheError> {
+        Ok(self
+            .read::<DetailsEntry>(&details_file(uuid))
+            .await?
+            .and_then(|entry| {
+                Some(Cached {
+                    value: decode_details(entry.value)?,
+                    updated_at: entry.updated_at,
+                })
+            }))
+    }
+
+    /// Saves the event, and forgets those opened long ago.
+    pub async fn save_details(&self, details: &EventDetails) -> Result<(), CacheError> {
+        self.write(&details_file(details.event.id), &encode_details(details))
+            .await?;
+        let now = SystemTime::now();
+        remove_files(&self.directory.join(DETAILS_DIRECTORY), |_, modified| {
+            now.duration_since(modified)
+                .is_ok_and(|age| age > DETAILS_MAX_AGE)
+        })
+        .await
+        .map_err(CacheError::Io)
+    }
+
+    async fn read<T: DeserializeOwned>(&self, name: &str) -> Result<Option<Entry<T>>, CacheError> {
+        match tokio::fs::read(self.directory.join(name)).await {
+            Ok(content) => serde_json::from_slice(&content)
+                .map(Some)
+                .map_err(CacheError::Json),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(CacheError::Io(err)),
+        }
+    }
+
+    async fn write<T: Serialize>(&self, name: &str, value: &T) -> Result<(), CacheError> {
+        let content = serde_json::to_vec(&Entry {
+            updated_at: Utc::now(),
+            value,
+        })
+        .map_err(CacheError::Json)?;
+        write_atomically(&self.directory.join(name), &content)
+            .await
+            .map_err(CacheError::Io)
+    }
+}
+
+/// Goes through a temporary file so that an interrupted write never leaves
+/// half a file behind.
+pub(super) async fn write_atomically(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    if let Some(directory) = path.parent() {
+        tokio::fs::create_dir_all(directory).await?;
+    }
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+    tokio::fs::write(&temporary_path, content).await?;
+    tokio::fs::rename(&temporary_path, path).await
+}
+
+/// Removes the files of `directory` for which `is_unwanted` holds, given
+/// their name and when they were last written.
+pub(super) async fn remove_files(
+    directory: &Path,
+    is_unwanted: impl Fn(&str, SystemTime) -> bool,
+) -> std::io::Result<()> {
+    let mut entries = match tokio::fs::read_dir(directory).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        let name = entry.file_name();
+        if metadata.is_file() && is_unwanted(&name.to_string_lossy(), metadata.modified()?) {
+            tokio::fs::remove_file(entry.path()).await?;
+        }
+    }
+    Ok(())
+}
+
+fn event_file(uuid: Uuid) -> String {
+    format!("{}/{}.json", EVENTS_DIRECTORY, uuid)
+}
+
+fn details_file(uuid: Uuid) -> String {
+    format!("{}/{}.json", DETAILS_DIRECTORY, uuid)
+}
+
+/// Name of the directory of an instance, e.g. `mobilizon.fr` or
+/// `localhost_4000`.
+fn instance_key(instance_url: &Url) -> String {
+    let host = instance_url.host_str().unwrap_or("unknown");
+    match instance_url.port() {
+        Some(port) => format!("{}_{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+fn encode_event(event: &Event) -> EventEntry {
+    EventEntry {
+        id: event.id,
+        title: event.title.clone(),
+        description: event.description.clone(),
+        picture_url: event.picture_url.as_ref().map(Url::to_string),
+        begins_on: event.begins_on.to_utc(),
+        ends_on: event.ends_on.to_utc(),
+        status: event.status.as_str().to_string(),
+        time_zone: event.time_zone.clone(),
+    }
+}
+
+fn decode_event(entry: EventEntry) -> Option<Event> {
+    let picture_url = match entry.picture_url {
+        Some(url) => Some(Url::parse(&url).ok()?),
+        None => None,
+    };
+    Some(Event {
+        id: entry.id,
+        title: entry.title,
+        description: entry.description,
+        picture_url,
+        begins_on: DateTime::new(entry.begins_on),
+        ends_on: DateTime::new(entry.ends_on),
+        status: EventStatus::from_str(&entry.status).ok()?,
+        time_zone: entry.time_zone,
+    })
+}
+
+fn encode_details(details: &EventDetails) -> DetailsEntry {
+    let (join_options, external_participation_url) = match &details.join_options {
+        JoinOptions::Free => ("FREE", None),
+        JoinOptions::Restricted => ("RESTRICTED", None),
+        JoinOptions::Invite => ("INVITE", None),
+        JoinOptions::External(url) => ("EXTERNAL", url.as_ref().map(Url::to_string)),
+    };
+    DetailsEntry {
+        event: encode_event(&details.event),
+        id: details.id.clone(),
+        url: details.url.as_ref().map(Url::to_string),
+        category: details.category.clone(),
+        language: details.language.clone(),
+        tags: details.tags.clone(),
+        physical_address: details
+            .physical_address
+            .as_ref()
+            .map(|address| AddressEntry {
+                description: address.description.clone(),
+                street: address.street.clone(),
+                postal_code: address.postal_code.clone(),
+                locality: address.locality.clone(),
+                region: address.region.clone(),
+                country: address.country.clone(),
+            }),
+        online_address: details.online_address.as_ref().map(Url::to_string),
+        organizer: details.organizer.as_ref().map(|actor| ActorEntry {
+            name: actor.name.clone(),
+            username: actor.username.clone(),
+            domain: actor.domain.clone(),
+        }),
+        going: details.participant_stats.going,
+        not_approved: details.participant_stats.not_approved,
+        maximum_attendee_capacity: details.maximum_attendee_capacity,
+        join_options: join_options.to_string(),
+        external_participation_url,
+    }
+}
+
+fn decode_details(entry: DetailsEntry) -> Option<EventDetails> {
+    let join_options = match entry.join_options.as_str() {
+        "FREE" => JoinOptions::Free,
+        "RESTRICTED" => JoinOptions::Restricted,
+        "INVITE" => JoinOptions::Invite,
+        "EXTERNAL" => JoinOptions::External(
+            entry
+                .external_participation_url
+                .and_then(|url| Url::parse(&url).ok()),
+        ),
+        _ => return None,
+    };
+    Some(EventDetails {
+        event: decode_event(entry.event)?,
+        id: entry.id,
+        url: entry.url.and_then(|url| Url::parse(&url).ok()),
+        category: entry.category,
+        language: entry.language,
+        tags: entry.tags,
+        physical_address: entry.physical_address.map(|address| Address {
+            description: address.description,
+            street: address.street,
+            postal_code: address.postal_code,
+            locality: address.locality,
+            region: address.region,
+            country: address.country,
+        }),
+        online_address: entry.online_address.and_then(|url| Url::parse(&url).ok()),
+        organizer: entry.organizer.map(|actor| Actor {
+            name: actor.name,
+            username: actor.username,
+            domain: actor.domain,
+        }),
+        participant_stats: ParticipantStats {
+            going: entry.going,
+            not_approved: entry.not_approved,
+        },
+        maximum_attendee_capacity: entry.maximum_attendee_capacity,
+        join_options,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(name: &str) -> (Cache, PathBuf) {
+        let base = std::env::temp_dir().join(format!(
+            "mobilisation-cache-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&base);
+        let cache = Cache::new(
+            &base,
+            &Url::parse("https://mobilizon.example:4000").unwrap(),
+        );
+        (cache, base)
+    }
+
+    fn event(title: &str) -> Event {
+        Event {
+            id: Uuid::parse_str("1e1a7bd4-f7c1-4fb4-92a6-8d5d2c3f4e5a").unwrap(),
+            title: title.to_string(),
+            description: Some("<p>Bring <b>food</b></p>".to_string()),
+            picture_url: Some(Url::parse("https://mobilizon.example/media/a.jpg").unwrap()),
+            begins_on: DateTime::new(Utc::now()),
+            ends_on: DateTime::new(Utc::now()),
+            status: EventStatus::Tentative,
+            time_zone: Some("Europe/Paris".to_string()),
+        }
+    }
+
+    #[test]
+    fn instances_get_a_directory_each() {
+        assert_eq!(
+            instance_key(&Url::parse("https://mobilizon.fr").unwrap()),
+            "mobilizon.fr"
+        );
+        assert_eq!(
+            instance_key(&Url::parse("http://localhost:4000/").unwrap()),
+            "localhost_4000"
+        );
+    }
+
+    #[tokio::test]
+    async fn nothing_is_cached_at_first() {
+        let (cache, base) = cache("empty");
+        assert!(cache.load_timeline().await.ok().unwrap().is_none());
+        assert!(cache.load_config().await.ok().unwrap().is_none());
+        let _ = std::fs::remove_dir_all(base);
+    }
+
+    #[tokio::test]
+    async fn timeline_survives_a_round_trip() {
+        let (cache, base) = cache("timeline");
+        let saved = vec![event("Picnic")];
+        assert!(cache.save_timeline(&saved).await.is_ok());
+        let loaded = cache.load_timeline().await.ok().unwrap().unwrap();
+        assert_eq!(loaded.value.len(), 1);
+        assert_eq!(loaded.value[0].id, saved[0].id);
+        assert_eq!(loaded.value[0].title, "Picnic");
+        assert_eq!(loaded.value[0].picture_url, saved[0].picture_url);
+        assert_eq!(loaded.value[0].begins_on, saved[0].begins_on);
+        assert_eq!(loaded.value[0].status, EventStatus::Tentative);
+        assert_eq!(loaded.value[0].description, saved[0].description);
+        assert_eq!(loaded.value[0].time_zone.as_deref(), Some("Europe/Paris"));
+        let _ = std::fs::remove_dir_all(base);
+    }
+
+    #[tokio::test]
+    async fn events_leaving_the_timeline_are_forgotten() {
+        let (cache, base) = cache("forgotten");
+        let left = Event {
+            id: Uuid::parse_str("7c0a3f5e-2b1d-4e8a-9f6c-3d2e1b0a9c8d").unwrap(),
+            ..event("Concert")
+        };
+        assert!(
+            cache
+                .save_timeline(std::slice::from_ref(&left))
+                .await
+                .is_ok()
+        );
+        assert!(cache.save_timeline(&[event("Picnic")]).await.is_ok());
+        let loaded = cache.load_timeline().await.ok().unwrap().unwrap();
+        assert_eq!(loaded.value.len(), 1);
+        assert_eq!(loaded.value[0].title, "Picnic");
+        assert!(
+            !base
+                .join("mobilizon.example_4000")
+                .join(event_file(left.id))
+                .exists()
+        );
+        let _ = std::fs::remove_dir_all(base);
+    }
+
+    #[tokio::test]
+    async fn details_are_kept_per_event() {
+        let (cache, base) = cache("details");
+        let saved = EventDetails {
+            event: event("Picnic"),
+            id: "42".to_string(),
+            tags: vec!["outdoors".to_string()],
+            organizer: Some(Actor {
+                name: Some("Alice".to_string()),
+                username: "alice".to_string(),
+                domain: None,
+            }),
+            participant_stats: ParticipantStats {
+                going: 3,
+                not_approved: 1,
+            },
+            join_options: JoinOptions::External(Some(
+                Url::parse("https://tickets.example/picnic").unwrap(),
+            )),
+            ..Default::default()
+        };
+        assert!(cache.save_details(&saved).await.is_ok());
+        let loaded = cache
+            .load_details(saved.event.id)
+            .await
+            .ok()
+            .unwrap()
+            .unwrap()
+            .value;
+        assert_eq!(loaded.event.title, "Picnic");
+        assert_eq!(loaded.id, "42");
+        assert_eq!(loaded.tags, saved.tags);
+        assert_eq!(loaded.organizer, saved.organizer);
+        assert_eq!(loaded.participant_stats, saved.participant_stats);
+        assert_eq!(loaded.join_options, saved.join_options);
+        let other = Uuid::parse_str("7c0a3f5e-2b1d-4e8a-9f6c-3d2e1b0a9c8d").unwrap();
+        assert!(cache.load_details(other).await.ok().unwrap().is_none());
+        let _ = std::fs::remove_dir_all(base);
+    }
+
+    #[tokio::test]
+    async fn config_survives_a_round_trip() {
+        let (cache, base) = cache("config");
+        let saved = FetchConfigResponse {
+            instance_version: InstanceVersion::from_str("5.1.0").ok().unwrap(),
+            categories: vec![Category {
+                id: "MUSIC".to_string(),
+                label: "Music".to_string(),
+            }],
+            languages: vec![Language::new("fr")],
+        };
+        assert!(cache.save_config(&saved).await.is_ok());
+        let loaded = cache.load_config().await.ok().unwrap().unwrap();
+        assert_eq!(loaded.value.instance_version, saved.instance_version);
+        assert_eq!(loaded.value.categories[0].id, "MUSIC");
+        assert_eq!(loaded.value.languages, saved.languages);
+        let _ = std::fs::remove_dir_all(base);
+    }
+}
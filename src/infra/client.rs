@@ -0,0 +1,367 @@
+use bytes::Bytes;
+use graphql_client::{GraphQLQuery, QueryBody};
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use url::Url;
+use uuid::Uuid;
+
+use crate::config::VERSION;
+use crate::core::event_details::EventDetails;
+use crate::core::event_search::EventSearch;
+use crate::core::participation::{EventParticipation, ParticipantRole, Participation};
+use crate::infra::auth::{AuthError, Session};
+use crate::infra::config::{ConfigFetchError, FetchConfigResponse};
+use crate::infra::event_details::EventDetailsFetchError;
+use crate::infra::events::{EventPictureFetchError, EventsFetchError, EventsPage};
+use crate::infra::instance::api_url;
+use crate::infra::participation::ParticipationError;
+use crate::infra::pictures::PictureLoader;
+use crate::infra::{auth, config, event_details, events, participation};
+
+/// Futures are boxed so that the api can be shared as a `dyn MobilizonApi`
+/// and its requests spawned on the runtime.
+pub type ApiFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Everything the application asks a Mobilizon instance. Widgets only know
+/// about this trait so that tests can answer in place of a real instance.
+pub trait MobilizonApi: Debug + Send + Sync {
+    fn fetch_config(&self) -> ApiFuture<'_, Result<FetchConfigResponse, ConfigFetchError>>;
+
+    /// Fetches one page (starting at 1) of upcoming events matching the
+    /// search, along with the total number of events matching it.
+    fn fetch_events<'a>(
+        &'a self,
+        search: &'a EventSearch,
+        page: i64,
+        limit: i64,
+    ) -> ApiFuture<'a, Result<EventsPage, EventsFetchError>>;
+
+    fn fetch_event_details(
+        &self,
+        uuid: Uuid,
+    ) -> ApiFuture<'_, Result<EventDetails, EventDetailsFetchError>>;
+
+    fn fetch_event_picture<'a>(
+        &'a self,
+        picture_url: &'a Url,
+    ) -> ApiFuture<'a, Result<Bytes, EventPictureFetchError>>;
+
+    /// Every request made after this is authenticated as the user.
+    fn login<'a>(
+        &'a self,
+        email: &'a str,
+        password: &'a str,
+    ) -> ApiFuture<'a, Result<(), AuthError>>;
+
+    fn logout(&self) -> ApiFuture<'_, Result<(), AuthError>>;
+
+    /// Only makes sense once logged in.
+    fn fetch_participation<'a>(
+        &'a self,
+        event_id: &'a str,
+    ) -> ApiFuture<'a, Result<Participation, ParticipationError>>;
+
+    fn join_event<'a>(
+        &'a self,
+        event_id: &'a str,
+        actor_id: &'a str,
+    ) -> ApiFuture<'a, Result<Option<ParticipantRole>, ParticipationError>>;
+
+    fn leave_event<'a>(
+        &'a self,
+        event_id: &'a str,
+        actor_id: &'a str,
+    ) -> ApiFuture<'a, Result<(), ParticipationError>>;
+
+    /// Everything the logged in user takes part in or organizes, past events
+    /// included.
+    fn fetch_my_events(&self)
+    -> ApiFuture<'_, Result<Vec<EventParticipation>, ParticipationError>>;
+}
+
+/// What happened to the session of the user
+pub enum SessionChange {
+    /// The refresh token changed and should be saved in its place
+    Renewed(String),
+    /// The user logged out
+    Ended,
+    /// The server refused the session, the user has to log in again
+    Expired,
+}
+
+/// Told whenever the session changes so that it can be saved.
+pub type SessionListener = Box<dyn Fn(SessionChange) + Send + Sync>;
+
+/// The refresh token saved by a previous run, read when first needed.
+pub type SavedSession = ApiFuture<'static, Option<String>>;
+
+/// Errors when sending a request on behalf of the user
+pub enum RequestError {
+    HttpError(Box<reqwest::Error>),
+    /// The session could not be renewed, the request was not sent
+    SessionExpired,
+}
+
+/// Gives up on an instance that takes this long to answer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Talks to one Mobilizon instance over http.
+pub struct MobilizonClient {
+    http_client: reqwest::Client,
+    graphql_url: Url,
+    /// Held while refreshing so that concurrent requests wait for the new
+    /// tokens instead of refreshing the same session twice.
+    session: Mutex<Option<Session>>,
+    /// Taken the first time the session is needed
+    saved_session: std::sync::Mutex<Option<SavedSession>>,
+    session_listener: Option<SessionListener>,
+    pictures: PictureLoader,
+}
+
+impl Debug for MobilizonClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MobilizonClient")
+            .field("graphql_url", &self.graphql_url.as_str())
+            .finish_non_exhaustive()
+    }
+}
+
+impl MobilizonClient {
+    pub fn new(instance_url: &Url) -> Self {
+        let http_client = reqwest::Client::builder()
+            .user_agent(format!("Mobilisation/{}", VERSION))
+            .timeout(REQUEST_TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .expect("Failed to build the http client");
+        Self {
+            http_client,
+            graphql_url: api_url(instance_url),
+            session: Mutex::new(None),
+            saved_session: std::sync::Mutex::new(None),
+            session_listener: None,
+            pictures: PictureLoader::new(None),
+        }
+    }
+
+    /// Keeps the pictures of events in `directory` across runs.
+    pub fn with_picture_directory(self, directory: PathBuf) -> Self {
+        Self {
+            pictures: PictureLoader::new(Some(directory)),
+            ..self
+        }
+    }
+
+    pub fn with_session_listener(mut self, session_listener: SessionListener) -> Self {
+        self.session_listener = Some(session_listener);
+        self
+    }
+
+    /// Picks up the session of a previous run once its refresh token has
+    /// been read, which takes a while when the keyring has to be unlocked.
+    pub fn with_saved_session(self, saved_session: SavedSession) -> Self {
+        Self {
+            saved_session: std::sync::Mutex::new(Some(saved_session)),
+            ..self
+        }
+    }
+
+    /// Sends the query, authenticated when the user is logged in. An access
+    /// token the server turns down is refreshed and the query sent again,
+    /// once.
+    pub(super) async fn send_graphql<Q: GraphQLQuery>(
+        &self,
+        variables: Q::Variables,
+    ) -> Result<reqwest::Response, RequestError> {
+        let query = Q::build_query(variables);
+        let access_token = self.access_token(None).await?;
+        let response = self
+            .send_query(&query, access_token.as_deref())
+            .await
+            .map_err(|e| RequestError::HttpError(Box::new(e)))?;
+        if response.status() != StatusCode::UNAUTHORIZED || access_token.is_none() {
+            return Ok(response);
+        }
+        let access_token = self.access_token(access_token).await?;
+        self.send_query(&query, access_token.as_deref())
+            .await
+            .map_err(|e| RequestError::HttpError(Box::new(e)))
+    }
+
+    pub(super) async fn send_query<V: Serialize>(
+        &self,
+        query: &QueryBody<V>,
+        access_token: Option<&str>,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let request = self.http_client.post(self.graphql_url.clone()).json(query);
+        match access_token {
+            Some(access_token) => request.bearer_auth(access_token),
+            None => request,
+        }
+        .send()
+        .await
+    }
+
+    /// A request for the query that never carries the access token, for the
+    /// ones that create it.
+    pub(super) fn anonymous_graphql_request<Q: GraphQLQuery>(
+        &self,
+        variables: Q::Variables,
+    ) -> reqwest::RequestBuilder {
+        self.http_client
+            .post(self.graphql_url.clone())
+            .json(&Q::build_query(variables))
+    }
+
+    /// The access token of the session, `None` when logged out. It is
+    /// refreshed first when about to expire, or when it is the `rejected`
+    /// one and no other request refreshed it meanwhile.
+    async fn access_token(&self, rejected: Option<String>) -> Result<Option<String>, RequestError> {
+        let mut session = self.session.lock().await;
+        self.resume_saved_session(&mut session).await;
+        let Some(current) = session.as_ref() else {
+            return Ok(None);
+        };
+        if !current.needs_refresh(chrono::Utc::now())
+            && (rejected.is_none() || current.access_token != rejected)
+        {
+            return Ok(current.access_token.clone());
+        }
+        match auth::refresh(self, &current.refresh_token).await {
+            Ok(refreshed) => {
+                self.notify_session(SessionChange::Renewed(refreshed.refresh_token.clone()));
+                let access_token = refreshed.access_token.clone();
+                *session = Some(refreshed);
+                Ok(access_token)
+            }
+            // The server is unreachable, the session may still be fine
+            Err(AuthError::HttpError(err)) => Err(RequestError::HttpError(err)),
+            // The server refused the refresh token, the session is over
+            Err(_) => {
+                *session = None;
+                self.notify_session(SessionChange::Expired);
+                Err(RequestError::SessionExpired)
+            }
+        }
+    }
+
+    /// Reads the session saved by a previous run, unless done already.
+    async fn resume_saved_session(&self, session: &mut Option<Session>) {
+        let saved_session = self.saved_session.lock().unwrap().take();
+        if let Some(saved_session) = saved_session {
+            match saved_session.await {
+                Some(refresh_token) => *session = Some(Session::resume(refresh_token)),
+                // Logged in last time, but the refresh token is gone
+                None => self.notify_session(SessionChange::Expired),
+            }
+        }
+    }
+
+    fn notify_session(&self, change: SessionChange) {
+        if let Some(session_listener) = &self.session_listener {
+            session_listener(change);
+        }
+    }
+
+    /// A plain request for anything that is not GraphQL, like pictures.
+    pub(super) fn get(&self, url: &Url) -> reqwest::RequestBuilder {
+        self.http_client.get(url.clone())
+    }
+}
+
+impl MobilizonApi for MobilizonClient {
+    fn fetch_config(&self) -> ApiFuture<'_, Result<FetchConfigResponse, ConfigFetchError>> {
+        Box::pin(config::fetch_config(self))
+    }
+
+    fn fetch_events<'a>(
+        &'a self,
+        search: &'a EventSearch,
+        page: i64,
+        limit: i64,
+    ) -> ApiFuture<'a, Result<EventsPage, EventsFetchError>> {
+        Box::pin(events::fetch_events(self, search, page, limit))
+    }
+
+    fn fetch_event_details(
+        &self,
+        uuid: Uuid,
+    ) -> ApiFuture<'_, Result<EventDetails, EventDetailsFetchError>> {
+        Box::pin(event_details::fetch_event_details(self, uuid))
+    }
+
+    fn fetch_event_picture<'a>(
+        &'a self,
+        picture_url: &'a Url,
+    ) -> ApiFuture<'a, Result<Bytes, EventPictureFetchError>> {
+        Box::pin(self.pictures.load(self, picture_url))
+    }
+
+    fn login<'a>(
+        &'a self,
+        email: &'a str,
+        password: &'a str,
+    ) -> ApiFuture<'a, Result<(), AuthError>> {
+        Box::pin(async move {
+            let session = auth::login(self, email, password).await?;
+            let mut current = self.session.lock().await;
+            // Replaces the session of the previous run, read or not
+            self.saved_session.lock().unwrap().take();
+            self.notify_session(SessionChange::Renewed(session.refresh_token.clone()));
+            *current = Some(session);
+            Ok(())
+        })
+    }
+
+    fn logout(&self) -> ApiFuture<'_, Result<(), AuthError>> {
+        Box::pin(async move {
+            // Forgotten here even if the server could not be told about it,
+            // and never refreshed only to be revoked
+            let mut current = self.session.lock().await;
+            self.resume_saved_session(&mut current).await;
+            let Some(session) = current.take() else {
+                return Ok(());
+            };
+            drop(current);
+            let result = auth::logout(self, &session).await;
+            self.notify_session(SessionChange::Ended);
+            result
+        })
+    }
+
+    fn fetch_participation<'a>(
+        &'a self,
+        event_id: &'a str,
+    ) -> ApiFuture<'a, Result<Participation, ParticipationError>> {
+        Box::pin(participation::fetch_participation(self, event_id))
+    }
+
+    fn join_event<'a>(
+        &'a self,
+        event_id: &'a str,
+        actor_id: &'a str,
+    ) -> ApiFuture<'a, Result<Option<ParticipantRole>, ParticipationError>> {
+        Box::pin(participation::join_event(self, event_id, actor_id))
+    }
+
+    fn leave_event<'a>(
+        &'a self,
+        event_id: &'a str,
+        actor_id: &'a str,
+    ) -> ApiFuture<'a, Result<(), ParticipationError>> {
+        Box::pin(participation::leave_event(self, event_id, actor_id))
+    }
+
+    fn fetch_my_events(
+        &self,
+    ) -> ApiFuture<'_, Result<Vec<EventParticipation>, ParticipationError>> {
+        Box::pin(participation::fetch_my_events(self))
+    }
+}
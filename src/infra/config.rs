@@ -1,10 +1,12 @@
 use std::str::FromStr;
 use graphql_client::{GraphQLQuery, Response};
 use reqwest;
-use url::Url;
 
 use crate::core::instance_version::*;
 use crate::core::category::*;
+use crate::core::language::Language;
+use crate::infra::client::{MobilizonClient, RequestError};
+use crate::infra::graphql::{GraphQLErrors, check_errors};
 
 #[derive(GraphQLQuery)]
 #[graphql(
@@ -18,9 +20,20 @@ pub enum ConfigFetchError {
     HttpError(Box<reqwest::Error>),
     InstanceVersionParsingError(InstanceVersionParsingError),
     MissingField(String),
+    /// Carries the configuration when the server sent all of it anyway
+    GraphQLErrors(GraphQLErrors<FetchConfigResponse>),
+    /// The user has to log in again
+    SessionExpired,
 }
 
-type Language = String;
+impl From<RequestError> for ConfigFetchError {
+    fn from(err: RequestError) -> Self {
+        match err {
+            RequestError::HttpError(err) => ConfigFetchError::HttpError(err),
+            RequestError::SessionExpired => ConfigFetchError::SessionExpired,
+        }
+    }
+}
 
 #[derive(Default)]
 pub struct FetchConfigResponse {
@@ -29,22 +42,25 @@ pub struct FetchConfigResponse {
     pub languages: Vec<Language>
 }
 
-pub async fn fetch_config(
-    instance_url: &Url,
-    http_client: &reqwest::Client
+pub(super) async fn fetch_config(
+    client: &MobilizonClient
 ) -> Result<FetchConfigResponse, ConfigFetchError> {
-    let request = ConfigQuery::build_query(config_query::Variables {});
-    let response = http_client
-        .post(instance_url.to_string())
-        .json(&request)
-        .send()
-        .await
-        .map_err(|e| ConfigFetchError::HttpError(Box::new(e)))?
+    let response = client
+        .send_graphql::<ConfigQuery>(config_query::Variables {})
+        .await?
         .json::<Response<config_query::ResponseData>>()
         .await
         .map_err(|e| ConfigFetchError::HttpError(Box::new(e)))?;
-    let version: &String = response
-        .data
+    let data = check_errors(response).map_err(|errors| {
+        ConfigFetchError::GraphQLErrors(errors.map_data(|data| decode_config(Some(data)).ok()))
+    })?;
+    decode_config(data)
+}
+
+fn decode_config(
+    data: Option<config_query::ResponseData>
+) -> Result<FetchConfigResponse, ConfigFetchError> {
+    let version: &String = data
         .as_ref()
         .and_then(|data| data.config.as_ref())
         .and_then(|config| config.version.as_ref())
@@ -53,8 +69,7 @@ pub async fn fetch_config(
         ))?;
     let instance_version = InstanceVersion::from_str(version)
         .map_err(|err| ConfigFetchError::InstanceVersionParsingError(err))?;
-    let categories = response
-        .data
+    let categories = data
         .as_ref()
         .and_then(|data| data.config.as_ref())
         .and_then(|config| config.event_categories.clone())
@@ -78,14 +93,14 @@ pub async fn fetch_config(
             })
         })
         .collect::<Result<Vec<Category>, ConfigFetchError>>()?;
-    let languages = response
-        .data
+    let languages = data
         .as_ref()
         .and_then(|data| data.config.as_ref())
         .and_then(|config| config.languages.clone())
         .map(|e|
             e.into_iter()
             .flatten()
+            .map(|code| Language::new(&code))
             .collect::<Vec<Language>>()
         )
         .ok_or(ConfigFetchError::MissingField("languages".to_string()))?;
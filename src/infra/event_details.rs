@@ -0,0 +1,298 @@
+use graphql_client::{GraphQLQuery, Response};
+use reqwest;
+use std::str::FromStr;
+use url::Url;
+/// The GraphQL dependency will look for the UUID type in caps
+use uuid::Uuid as UUID;
+
+use crate::core::event::{DateTime as EventDateTime, Event, EventStatus};
+use crate::core::event_details::{Actor, Address, EventDetails, ParticipantStats};
+use crate::core::participation::JoinOptions;
+use crate::infra::client::{MobilizonClient, RequestError};
+use crate::infra::graphql::{GraphQLErrors, check_errors, enum_name};
+
+type DateTime = chrono::DateTime<chrono::Utc>;
+type Timezone = String;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.json",
+    query_path = "graphql/event.graphql",
+    response_derives = "Debug"
+)]
+struct EventQuery;
+
+/// Errors when querying a single event
+pub enum EventDetailsFetchError {
+    HttpError(Box<reqwest::Error>),
+    MissingField(String),
+    NotFound(UUID),
+    GraphQLErrors(GraphQLErrors),
+    /// The user has to log in again
+    SessionExpired,
+}
+
+impl From<RequestError> for EventDetailsFetchError {
+    fn from(err: RequestError) -> Self {
+        match err {
+            RequestError::HttpError(err) => EventDetailsFetchError::HttpError(err),
+            RequestError::SessionExpired => EventDetailsFetchError::SessionExpired,
+        }
+    }
+}
+
+pub(super) async fn fetch_event_details(
+    client: &MobilizonClient,
+    uuid: UUID,
+) -> Result<EventDetails, EventDetailsFetchError> {
+    let response = client
+        .send_graphql::<EventQuery>(event_query::Variables { uuid })
+        .await?
+        .json::<Response<event_query::ResponseData>>()
+        .await
+        .map_err(|e| EventDetailsFetchError::HttpError(Box::new(e)))?;
+    let data = check_errors(response)
+        .map_err(|errors| EventDetailsFetchError::GraphQLErrors(errors.without_data()))?;
+    decode_event_details(data, uuid)
+}
+
+fn decode_event_details(
+    data: Option<event_query::ResponseData>,
+    uuid: UUID,
+) -> Result<EventDetails, EventDetailsFetchError> {
+    let event = data
+        .ok_or(EventDetailsFetchError::MissingField("data".to_string()))?
+        .event
+        .ok_or(EventDetailsFetchError::NotFound(uuid))?;
+    let id = event
+        .id
+        .ok_or(EventDetailsFetchError::MissingField("id".to_string()))?;
+    let title = event
+        .title
+        .ok_or(EventDetailsFetchError::MissingField("title".to_string()))?;
+    let begins_on = event
+        .begins_on
+        .ok_or(EventDetailsFetchError::MissingField("beginsOn".to_string()))?;
+    let ends_on = event
+        .ends_on
+        .ok_or(EventDetailsFetchError::MissingField("endsOn".to_string()))?;
+    let options = event.options;
+    // Either the group the event was published under, or the person who
+    // created it.
+    let organizer = event
+        .attributed_to
+        .and_then(|actor| actor_from(actor.name, actor.preferred_username, actor.domain))
+        .or(event
+            .organizer_actor
+            .and_then(|actor| actor_from(actor.name, actor.preferred_username, actor.domain)));
+    Ok(EventDetails {
+        event: Event {
+            id: event.uuid.unwrap_or(uuid),
+            title,
+            description: event.description.filter(|d| !d.is_empty()),
+            picture_url: event
+                .picture
+                .and_then(|picture| picture.url)
+                .and_then(|url| Url::from_str(&url).ok()),
+            begins_on: EventDateTime::new(begins_on),
+            ends_on: EventDateTime::new(ends_on),
+            status: match event.status {
+                Some(event_query::EventStatus::TENTATIVE) => EventStatus::Tentative,
+                Some(event_query::EventStatus::CANCELLED) => EventStatus::Cancelled,
+                _ => EventStatus::Confirmed,
+            },
+            time_zone: options
+                .as_ref()
+                .and_then(|options| options.timezone.clone())
+                .filter(|zone| !zone.is_empty()),
+        },
+        id,
+        url: event.url.and_then(|url| Url::from_str(&url).ok()),
+        category: event.category.as_ref().and_then(enum_name),
+        language: event.language.filter(|l| !l.is_empty()),
+        tags: event
+            .tags
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .filter_map(|tag| tag.title)
+            .collect(),
+        physical_address: event.physical_address.map(|address| Address {
+            description: address.description,
+            street: address.street,
+            postal_code: address.postal_code,
+            locality: address.locality,
+            region: address.region,
+            country: address.country,
+        }),
+        online_address: event
+            .online_address
+            .and_then(|address| Url::from_str(&address).ok()),
+        organizer,
+        participant_stats: event
+            .participant_stats
+            .map(|stats| ParticipantStats {
+                going: stats.going.unwrap_or_default(),
+                not_approved: stats.not_approved.unwrap_or_default(),
+            })
+            .unwrap_or_default(),
+        maximum_attendee_capacity: options
+            .and_then(|options| options.maximum_attendee_capacity)
+            .filter(|capacity| *capacity > 0),
+        join_options: match event.join_options {
+            Some(event_query::EventJoinOptions::RESTRICTED) => JoinOptions::Restricted,
+            Some(event_query::EventJoinOptions::INVITE) => JoinOptions::Invite,
+            Some(event_query::EventJoinOptions::EXTERNAL) => JoinOptions::External(
+                event
+                    .external_participation_url
+                    .and_then(|url| Url::from_str(&url).ok()),
+            ),
+            _ => JoinOptions::Free,
+        },
+    })
+}
+
+fn actor_from(
+    name: Option<String>,
+    username: Option<String>,
+    domain: Option<String>,
+) -> Option<Actor> {
+    username.map(|username| Actor {
+        name,
+        username,
+        domain: domain.filter(|d| !d.is_empty()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID_OF_EVENT: &str = "6a1ce2d4-3b0f-4a4e-9f43-3f0a4b9b8d27";
+
+    fn event_json() -> serde_json::Value {
+        serde_json::json!({
+            "id": "42",
+            "uuid": UUID_OF_EVENT,
+            "url": "https://example.org/events/6a1ce2d4-3b0f-4a4e-9f43-3f0a4b9b8d27",
+            "title": "Picnic",
+            "description": "<p>Bring <b>food</b></p>",
+            "beginsOn": "2025-01-01T10:00:00Z",
+            "endsOn": "2025-01-01T12:00:00Z",
+            "status": "TENTATIVE",
+            "category": "MEETING",
+            "language": "fr",
+            "onlineAddress": "https://example.org/visio",
+            "picture": { "url": "https://example.org/picture.png" },
+            "physicalAddress": {
+                "description": "Parc de la Tête d'Or",
+                "street": null,
+                "postalCode": "69006",
+                "locality": "Lyon",
+                "region": null,
+                "country": "France",
+            },
+            "organizerActor": {
+                "__typename": "Person",
+                "name": "Camille",
+                "preferredUsername": "camille",
+                "domain": null,
+            },
+            "attributedTo": {
+                "__typename": "Group",
+                "name": "Friends of the park",
+                "preferredUsername": "park",
+                "domain": "other.example.org",
+            },
+            "tags": [{ "title": "outdoors" }, null],
+            "participantStats": { "going": 12, "notApproved": 3 },
+            "options": { "maximumAttendeeCapacity": 20, "timezone": "Europe/Paris" },
+            "joinOptions": "EXTERNAL",
+            "externalParticipationUrl": "https://example.org/tickets",
+        })
+    }
+
+    fn decode(event: serde_json::Value) -> Result<EventDetails, EventDetailsFetchError> {
+        let data = serde_json::from_value(serde_json::json!({ "event": event })).unwrap();
+        decode_event_details(Some(data), UUID::parse_str(UUID_OF_EVENT).unwrap())
+    }
+
+    #[test]
+    fn complete_event_is_decoded() {
+        let details = decode(event_json()).ok().unwrap();
+        assert_eq!(details.id, "42");
+        assert_eq!(details.event.title, "Picnic");
+        assert_eq!(details.event.status, EventStatus::Tentative);
+        assert_eq!(details.event.time_zone.as_deref(), Some("Europe/Paris"));
+        assert_eq!(details.category.as_deref(), Some("MEETING"));
+        assert_eq!(details.tags, vec!["outdoors".to_string()]);
+        assert_eq!(
+            details.physical_address.unwrap().locality.as_deref(),
+            Some("Lyon")
+        );
+        // The group the event was published under goes first
+        assert_eq!(details.organizer.unwrap().username, "park");
+        assert_eq!(
+            details.participant_stats,
+            ParticipantStats {
+                going: 12,
+                not_approved: 3,
+            }
+        );
+        assert_eq!(details.maximum_attendee_capacity, Some(20));
+        assert_eq!(
+            details.join_options,
+            JoinOptions::External(Some(Url::parse("https://example.org/tickets").unwrap()))
+        );
+    }
+
+    #[test]
+    fn event_missing_optional_fields_is_decoded() {
+        let mut event = event_json();
+        [
+            "url",
+            "description",
+            "category",
+            "onlineAddress",
+            "picture",
+            "physicalAddress",
+            "attributedTo",
+            "tags",
+            "participantStats",
+            "options",
+            "joinOptions",
+        ]
+        .into_iter()
+        .for_each(|field| event[field] = serde_json::Value::Null);
+        event["language"] = serde_json::json!("");
+        let details = decode(event).ok().unwrap();
+        assert_eq!(details.event.description, None);
+        assert_eq!(details.event.time_zone, None);
+        assert_eq!(details.language, None);
+        assert!(details.tags.is_empty());
+        assert_eq!(details.physical_address, None);
+        // Without a group, the person who created the event organizes it
+        assert_eq!(details.organizer.unwrap().username, "camille");
+        assert_eq!(details.participant_stats, ParticipantStats::default());
+        assert_eq!(details.maximum_attendee_capacity, None);
+        assert_eq!(details.join_options, JoinOptions::Free);
+    }
+
+    #[test]
+    fn event_missing_a_required_field_is_rejected() {
+        let mut event = event_json();
+        event["beginsOn"] = serde_json::Value::Null;
+        assert!(matches!(
+            decode(event),
+            Err(EventDetailsFetchError::MissingField(field)) if field == "beginsOn"
+        ));
+    }
+
+    #[test]
+    fn missing_event_is_not_found() {
+        assert!(matches!(
+            decode(serde_json::Value::Null),
+            Err(EventDetailsFetchError::NotFound(_))
+        ));
+    }
+}
@@ -1,4 +1,3 @@
-use bytes::Bytes;
 use graphql_client::{GraphQLQuery, Response};
 use reqwest;
 use serde;
@@ -7,13 +6,30 @@ use url::Url;
 /// The GraphQL dependency will look for the UUID type in caps
 use uuid::Uuid as UUID;
 
-use crate::core::event::Event;
+use crate::core::event::{Event, EventStatus};
+use crate::core::event_search::EventSearch;
+use crate::core::pagination::Pagination;
+use crate::infra::client::{MobilizonApi, MobilizonClient, RequestError};
+use crate::infra::graphql::{GraphQLErrors, check_errors};
 
 /// Errors when querying the events
 pub enum EventsFetchError {
     HttpError(Box<reqwest::Error>),
     MissingField(String),
     MissingTotalResultsField,
+    /// Carries the events the server sent anyway
+    GraphQLErrors(GraphQLErrors<EventsPage>),
+    /// The user has to log in again
+    SessionExpired,
+}
+
+impl From<RequestError> for EventsFetchError {
+    fn from(err: RequestError) -> Self {
+        match err {
+            RequestError::HttpError(err) => EventsFetchError::HttpError(err),
+            RequestError::SessionExpired => EventsFetchError::SessionExpired,
+        }
+    }
 }
 
 /// Errors in one specific event already that has been already retrieved
@@ -21,9 +37,16 @@ pub enum EventDecodeError {
     InvalidPictureUrl(String),
     UnexpectedStructureOfEvent(UUID, String),
     EventWithNoId,
+    /// The server sent nothing in place of the event
+    MissingEvent,
 }
 
 type DateTime = chrono::DateTime<chrono::Utc>;
+type Timezone = String;
+
+/// Events of a page, each decoded on its own, and the total number of events
+/// matching the search.
+pub type EventsPage = (Vec<Result<Event, EventDecodeError>>, i64);
 
 #[derive(GraphQLQuery)]
 #[graphql(
@@ -33,98 +56,289 @@ type DateTime = chrono::DateTime<chrono::Utc>;
 )]
 struct SearchEventsQuery;
 
-pub async fn fetch_events(
-    http_client: &reqwest::Client,
-    graphql_url: &str,
-) -> Result<(Vec<Result<Event, EventDecodeError>>, i64), EventsFetchError> {
-    let time_now = chrono::Utc::now();
-    let request = SearchEventsQuery::build_query(search_events_query::Variables {
-        begins_on: time_now,
-    });
-    let response = http_client
-        .post(graphql_url)
-        .json(&request)
-        .send()
-        .await
-        .map_err(|e| EventsFetchError::HttpError(Box::new(e)))?
+pub(super) async fn fetch_events(
+    client: &MobilizonClient,
+    search: &EventSearch,
+    page: i64,
+    limit: i64,
+) -> Result<EventsPage, EventsFetchError> {
+    let response = client
+        .send_graphql::<SearchEventsQuery>(search_variables(search, page, limit))
+        .await?
         .json::<Response<search_events_query::ResponseData>>()
         .await
         .map_err(|e| EventsFetchError::HttpError(Box::new(e)))?;
-    let total_events_fetched = response
-        .data
-        .as_ref()
-        .and_then(|data| data.search_events.as_ref())
-        .map(|search_events| search_events.total)
+    let data = check_errors(response).map_err(|errors| {
+        EventsFetchError::GraphQLErrors(errors.map_data(|data| decode_page(Some(data)).ok()))
+    })?;
+    decode_page(data)
+}
+
+/// Every event matching the search, fetched page after page. Events that
+/// could not be decoded are left out, as on the timeline.
+pub async fn fetch_all_events(
+    api: &dyn MobilizonApi,
+    search: &EventSearch,
+) -> Result<Vec<Event>, EventsFetchError> {
+    let mut pagination = Pagination::default();
+    let mut events = Vec::new();
+    while let Some(page) = pagination.next_page() {
+        let (page_events, total) = api.fetch_events(search, page, pagination.limit).await?;
+        pagination.page_loaded(page_events.len(), total);
+        events.extend(page_events.into_iter().filter_map(Result::ok));
+    }
+    Ok(events)
+}
+
+/// Events missing from the page are kept as errors, so that the page still
+/// counts as many events as asked for.
+fn decode_page(
+    data: Option<search_events_query::ResponseData>,
+) -> Result<EventsPage, EventsFetchError> {
+    let search_events = data
+        .ok_or(EventsFetchError::MissingField("data".to_string()))?
+        .search_events
         .ok_or(EventsFetchError::MissingTotalResultsField)?;
-    let raw_events = response
-        .data
-        .ok_or(EventsFetchError::MissingField("data".to_string()))
-        .and_then(|data| {
-            data.search_events
-                .ok_or(EventsFetchError::MissingField("search_events".to_string()))
-        })
-        .and_then(|search_events| {
-            search_events
-                .elements
-                .into_iter()
-                .collect::<Option<Vec<_>>>()
-                .ok_or(EventsFetchError::MissingField("event".to_string()))
-        })?;
-    let events = raw_events
+    let events = search_events
+        .elements
         .into_iter()
-        .map(|e| match e {
-            search_events_query::SearchEventsQuerySearchEventsElements {
-                uuid: Some(id),
-                title: Some(title),
-                on: _,
-                begins_on: Some(begins_on),
-                ends_on: Some(ends_on),
-                picture:
-                    Some(search_events_query::SearchEventsQuerySearchEventsElementsPicture {
-                        url: Some(url),
-                    }),
-            } => Url::from_str(url.as_str())
-                .map_err(|_| EventDecodeError::InvalidPictureUrl(url))
-                .and_then(|picture_url| {
-                    Ok(Event {
-                        id,
-                        title,
-                        picture_url: Some(picture_url),
-                        begins_on: crate::core::event::DateTime::new(begins_on),
-                        ends_on: crate::core::event::DateTime::new(ends_on),
-                    })
-                }),
-            failed_structure @ search_events_query::SearchEventsQuerySearchEventsElements {
-                uuid: Some(id),
-                title: _,
-                on: _,
-                begins_on: _,
-                ends_on: _,
-                picture: _,
-            } => Err(EventDecodeError::UnexpectedStructureOfEvent(
-                id,
-                format!("{:?}", failed_structure),
-            )),
-            _ => Err(EventDecodeError::EventWithNoId),
+        .map(|element| {
+            element
+                .ok_or(EventDecodeError::MissingEvent)
+                .and_then(decode_event)
         })
         .collect::<Vec<Result<Event, EventDecodeError>>>();
-    Ok((events, total_events_fetched))
+    Ok((events, search_events.total))
+}
+
+/// Variables of the search for one page. Every page of a search starts from
+/// the same moment, otherwise the offsets of pages would move with it.
+fn search_variables(search: &EventSearch, page: i64, limit: i64) -> search_events_query::Variables {
+    search_events_query::Variables {
+        begins_on: search.begins_on.unwrap_or_else(chrono::Utc::now),
+        term: search.term.clone(),
+        category_one_of: one_of(search.categories.clone()),
+        language_one_of: one_of(search.languages.clone()),
+        boost_languages: one_of(search.boost_languages.clone()),
+        status_one_of: one_of(
+            search
+                .statuses
+                .iter()
+                .map(|status| match status {
+                    EventStatus::Confirmed => search_events_query::EventStatus::CONFIRMED,
+                    EventStatus::Tentative => search_events_query::EventStatus::TENTATIVE,
+                    EventStatus::Cancelled => search_events_query::EventStatus::CANCELLED,
+                })
+                .collect(),
+        ),
+        page,
+        limit,
+    }
+}
+
+/// Events without a picture are kept, the timeline shows a placeholder for
+/// them instead.
+fn decode_event(
+    element: search_events_query::SearchEventsQuerySearchEventsElements,
+) -> Result<Event, EventDecodeError> {
+    match element {
+        search_events_query::SearchEventsQuerySearchEventsElements {
+            uuid: Some(id),
+            title: Some(title),
+            on,
+            begins_on: Some(begins_on),
+            ends_on: Some(ends_on),
+            picture,
+            status,
+            options,
+        } => {
+            let picture_url = picture
+                .and_then(|picture| picture.url)
+                .map(|url| {
+                    Url::from_str(url.as_str())
+                        .map_err(|_| EventDecodeError::InvalidPictureUrl(url))
+                })
+                .transpose()?;
+            Ok(Event {
+                id,
+                title,
+                description: match on {
+                    search_events_query::SearchEventsQuerySearchEventsElementsOn::Event(event) => {
+                        event
+                            .description
+                            .filter(|description| !description.is_empty())
+                    }
+                    _ => None,
+                },
+                picture_url,
+                begins_on: crate::core::event::DateTime::new(begins_on),
+                ends_on: crate::core::event::DateTime::new(ends_on),
+                status: decode_status(status),
+                time_zone: options
+                    .and_then(|options| options.timezone)
+                    .filter(|zone| !zone.is_empty()),
+            })
+        }
+        failed_structure @ search_events_query::SearchEventsQuerySearchEventsElements {
+            uuid: Some(id),
+            ..
+        } => Err(EventDecodeError::UnexpectedStructureOfEvent(
+            id,
+            format!("{:?}", failed_structure),
+        )),
+        _ => Err(EventDecodeError::EventWithNoId),
+    }
+}
+
+/// An empty `...OneOf` filter is sent as null so that it does not restrict
+/// the search at all.
+fn one_of<T>(values: Vec<T>) -> Option<Vec<T>> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values)
+    }
+}
+
+/// Events are confirmed unless said otherwise, which is also how Mobilizon
+/// creates them.
+fn decode_status(status: Option<search_events_query::EventStatus>) -> EventStatus {
+    match status {
+        Some(search_events_query::EventStatus::TENTATIVE) => EventStatus::Tentative,
+        Some(search_events_query::EventStatus::CANCELLED) => EventStatus::Cancelled,
+        _ => EventStatus::Confirmed,
+    }
 }
 
 pub enum EventPictureFetchError {
     HttpError(Box<reqwest::Error>),
 }
 
-pub async fn fetch_event_picture(
-    http_client: &reqwest::Client,
-    picture_url: &url::Url,
-) -> Result<Bytes, EventPictureFetchError> {
-    http_client
-        .get(picture_url.to_string())
-        .send()
-        .await
-        .map_err(|e| EventPictureFetchError::HttpError(Box::new(e)))?
-        .bytes()
-        .await
-        .map_err(|e| EventPictureFetchError::HttpError(Box::new(e)))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::fake::FakeInstance;
+
+    fn element_json(picture: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "__typename": "Event",
+            "uuid": "6a1ce2d4-3b0f-4a4e-9f43-3f0a4b9b8d27",
+            "beginsOn": "2025-01-01T10:00:00Z",
+            "endsOn": "2025-01-01T12:00:00Z",
+            "picture": picture,
+            "title": "Picnic",
+            "status": "CONFIRMED",
+        })
+    }
+
+    fn element(
+        picture: serde_json::Value,
+    ) -> search_events_query::SearchEventsQuerySearchEventsElements {
+        serde_json::from_value(element_json(picture)).unwrap()
+    }
+
+    #[test]
+    fn event_without_picture_is_decoded() {
+        let event = decode_event(element(serde_json::Value::Null)).ok().unwrap();
+        assert_eq!(event.title, "Picnic");
+        assert_eq!(event.picture_url, None);
+    }
+
+    #[test]
+    fn event_with_picture_is_decoded() {
+        let event = decode_event(element(serde_json::json!({
+            "__typename": "Media",
+            "url": "https://example.org/picture.png",
+        })))
+        .ok()
+        .unwrap();
+        assert_eq!(
+            event.picture_url,
+            Some(Url::parse("https://example.org/picture.png").unwrap())
+        );
+    }
+
+    #[test]
+    fn description_of_event_is_decoded() {
+        let mut json = element_json(serde_json::Value::Null);
+        json["description"] = serde_json::json!("<p>Bring <b>food</b></p>");
+        let event = decode_event(serde_json::from_value(json).unwrap())
+            .ok()
+            .unwrap();
+        assert_eq!(
+            event.description.as_deref(),
+            Some("<p>Bring <b>food</b></p>")
+        );
+    }
+
+    #[test]
+    fn results_other_than_events_have_no_description() {
+        let mut json = element_json(serde_json::Value::Null);
+        json["__typename"] = serde_json::json!("EventResult");
+        let event = decode_event(serde_json::from_value(json).unwrap())
+            .ok()
+            .unwrap();
+        assert_eq!(event.description, None);
+    }
+
+    #[test]
+    fn events_missing_from_a_page_are_kept_as_errors() {
+        let data = serde_json::from_value(serde_json::json!({
+            "searchEvents": {
+                "total": 2,
+                "elements": [
+                    element_json(serde_json::Value::Null),
+                    null,
+                ],
+            },
+        }))
+        .unwrap();
+        let (events, total) = decode_page(Some(data)).ok().unwrap();
+        assert_eq!(total, 2);
+        assert!(events[0].is_ok());
+        assert!(matches!(events[1], Err(EventDecodeError::MissingEvent)));
+    }
+
+    #[test]
+    fn pages_of_a_search_start_from_the_same_moment() {
+        let search = EventSearch {
+            begins_on: Some("2025-01-01T10:00:00Z".parse().unwrap()),
+            ..Default::default()
+        };
+        let first = search_variables(&search, 1, 20);
+        let second = search_variables(&search, 2, 20);
+        assert_eq!(first.begins_on, second.begins_on);
+        assert_eq!(first.begins_on, search.begins_on.unwrap());
+    }
+
+    #[tokio::test]
+    async fn all_pages_of_a_search_are_fetched() {
+        let instance = FakeInstance {
+            events: (0..45)
+                .map(|index| Event {
+                    title: format!("Event {}", index),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let events = fetch_all_events(&instance, &EventSearch::default())
+            .await
+            .ok()
+            .unwrap();
+        assert_eq!(events.len(), 45);
+        assert_eq!(events[44].title, "Event 44");
+    }
+
+    #[test]
+    fn event_with_invalid_picture_url_is_rejected() {
+        let result = decode_event(element(serde_json::json!({
+            "__typename": "Media",
+            "url": "not a url",
+        })));
+        assert!(matches!(
+            result,
+            Err(EventDecodeError::InvalidPictureUrl(_))
+        ));
+    }
 }
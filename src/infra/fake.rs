@@ -0,0 +1,139 @@
+use bytes::Bytes;
+use url::Url;
+use uuid::Uuid;
+
+use crate::core::event::Event;
+use crate::core::event_details::EventDetails;
+use crate::core::event_search::EventSearch;
+use crate::core::instance_version::InstanceVersion;
+use crate::core::participation::{EventParticipation, ParticipantRole, Participation};
+use crate::infra::auth::AuthError;
+use crate::infra::client::{ApiFuture, MobilizonApi};
+use crate::infra::config::{ConfigFetchError, FetchConfigResponse};
+use crate::infra::event_details::EventDetailsFetchError;
+use crate::infra::events::{EventPictureFetchError, EventsFetchError, EventsPage};
+use crate::infra::graphql::GraphQLErrors;
+use crate::infra::participation::ParticipationError;
+
+/// Answers in place of a Mobilizon instance, one where nobody can log in and
+/// every search finds the same events.
+#[derive(Debug, Default)]
+pub struct FakeInstance {
+    pub version: InstanceVersion,
+    pub events: Vec<Event>,
+}
+
+/// What the server answers to requests it refuses.
+fn refusal(message: &str) -> GraphQLErrors {
+    GraphQLErrors {
+        errors: vec![graphql_client::Error {
+            message: message.to_string(),
+            locations: None,
+            path: None,
+            extensions: None,
+        }],
+        partial_data: None,
+    }
+}
+
+fn logged_out<T>() -> ApiFuture<'static, Result<T, ParticipationError>>
+where
+    T: Send + 'static,
+{
+    Box::pin(async {
+        Err(ParticipationError::GraphQLErrors(refusal(
+            "You need to be logged in",
+        )))
+    })
+}
+
+impl MobilizonApi for FakeInstance {
+    fn fetch_config(&self) -> ApiFuture<'_, Result<FetchConfigResponse, ConfigFetchError>> {
+        let instance_version = self.version;
+        Box::pin(async move {
+            Ok(FetchConfigResponse {
+                instance_version,
+                ..Default::default()
+            })
+        })
+    }
+
+    fn fetch_events<'a>(
+        &'a self,
+        _search: &'a EventSearch,
+        page: i64,
+        limit: i64,
+    ) -> ApiFuture<'a, Result<EventsPage, EventsFetchError>> {
+        Box::pin(async move {
+            let events = self
+                .events
+                .iter()
+                .skip(((page - 1) * limit) as usize)
+                .take(limit as usize)
+                .cloned()
+                .map(Ok)
+                .collect();
+            Ok((events, self.events.len() as i64))
+        })
+    }
+
+    fn fetch_event_details(
+        &self,
+        uuid: Uuid,
+    ) -> ApiFuture<'_, Result<EventDetails, EventDetailsFetchError>> {
+        Box::pin(async move { Err(EventDetailsFetchError::NotFound(uuid)) })
+    }
+
+    /// Pictures all come back empty, like broken ones.
+    fn fetch_event_picture<'a>(
+        &'a self,
+        _picture_url: &'a Url,
+    ) -> ApiFuture<'a, Result<Bytes, EventPictureFetchError>> {
+        Box::pin(async { Ok(Bytes::new()) })
+    }
+
+    fn login<'a>(
+        &'a self,
+        _email: &'a str,
+        _password: &'a str,
+    ) -> ApiFuture<'a, Result<(), AuthError>> {
+        Box::pin(async {
+            Err(AuthError::GraphQLErrors(refusal(
+                "Impossible to authenticate, either your email or password are invalid.",
+            )))
+        })
+    }
+
+    fn logout(&self) -> ApiFuture<'_, Result<(), AuthError>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn fetch_participation<'a>(
+        &'a self,
+        _event_id: &'a str,
+    ) -> ApiFuture<'a, Result<Participation, ParticipationError>> {
+        logged_out()
+    }
+
+    fn join_event<'a>(
+        &'a self,
+        _event_id: &'a str,
+        _actor_id: &'a str,
+    ) -> ApiFuture<'a, Result<Option<ParticipantRole>, ParticipationError>> {
+        logged_out()
+    }
+
+    fn leave_event<'a>(
+        &'a self,
+        _event_id: &'a str,
+        _actor_id: &'a str,
+    ) -> ApiFuture<'a, Result<(), ParticipationError>> {
+        logged_out()
+    }
+
+    fn fetch_my_events(
+        &self,
+    ) -> ApiFuture<'_, Result<Vec<EventParticipation>, ParticipationError>> {
+        logged_out()
+    }
+}
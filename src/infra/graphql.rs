@@ -0,0 +1,102 @@
+use graphql_client::{Error, Response};
+
+/// Errors listed by the server in the `errors` field of a GraphQL response,
+/// with what it still sent of the data, for callers that can show part of it
+#[derive(Debug)]
+pub struct GraphQLErrors<T = ()> {
+    pub errors: Vec<Error>,
+    pub partial_data: Option<T>,
+}
+
+impl<T> GraphQLErrors<T> {
+    /// Turns the partial data into what callers make of it, `None` when
+    /// nothing can be made of it.
+    pub fn map_data<U>(self, f: impl FnOnce(T) -> Option<U>) -> GraphQLErrors<U> {
+        GraphQLErrors {
+            errors: self.errors,
+            partial_data: self.partial_data.and_then(f),
+        }
+    }
+
+    /// Only keeps whether there was partial data, for callers that cannot
+    /// show any.
+    pub fn without_data(self) -> GraphQLErrors {
+        self.map_data(|_| Some(()))
+    }
+}
+
+/// Takes the data out of the response, failing as soon as the server reports
+/// an error, since what is missing cannot be told apart from what does not
+/// exist. Whatever data it sent then comes with the errors.
+pub fn check_errors<T>(response: Response<T>) -> Result<Option<T>, GraphQLErrors<T>> {
+    match response.errors {
+        Some(errors) if !errors.is_empty() => Err(GraphQLErrors {
+            errors,
+            partial_data: response.data,
+        }),
+        _ => Ok(response.data),
+    }
+}
+
+/// Name of a GraphQL enum value as sent by the API, e.g. "ARTS".
+pub fn enum_name<T: serde::Serialize>(value: &T) -> Option<String> {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => Some(name),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(json: serde_json::Value) -> Response<serde_json::Value> {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn response_without_errors_passes() {
+        let response = response(serde_json::json!({ "data": { "config": {} } }));
+        assert!(check_errors(response).is_ok());
+    }
+
+    #[test]
+    fn empty_errors_pass() {
+        let response = response(serde_json::json!({ "data": {}, "errors": [] }));
+        assert!(check_errors(response).is_ok());
+    }
+
+    #[test]
+    fn errors_are_kept_with_their_path() {
+        let response = response(serde_json::json!({
+            "data": null,
+            "errors": [{
+                "message": "Event not found",
+                "path": ["event"],
+                "extensions": { "code": "not_found" },
+            }],
+        }));
+        let errors = check_errors(response).unwrap_err();
+        assert!(errors.partial_data.is_none());
+        assert_eq!(errors.errors.len(), 1);
+        assert_eq!(errors.errors[0].message, "Event not found");
+        assert_eq!(
+            errors.errors[0].path,
+            Some(vec![graphql_client::PathFragment::Key("event".to_string())])
+        );
+    }
+
+    #[test]
+    fn partial_data_is_reported() {
+        let response = response(serde_json::json!({
+            "data": { "searchEvents": null },
+            "errors": [{ "message": "Internal server error" }],
+        }));
+        let errors = check_errors(response).unwrap_err();
+        assert_eq!(
+            errors.partial_data,
+            Some(serde_json::json!({ "searchEvents": null }))
+        );
+        assert_eq!(errors.without_data().partial_data, Some(()));
+    }
+}
@@ -0,0 +1,115 @@
+use url::Url;
+
+use crate::core::instance_version::InstanceVersion;
+use crate::infra::client::{MobilizonApi, MobilizonClient};
+use crate::infra::config::ConfigFetchError;
+
+/// Errors when checking that an address points to a usable Mobilizon instance
+pub enum InstanceValidationError {
+    InvalidUrl(url::ParseError),
+    ConfigFetchError(ConfigFetchError),
+    UnsupportedVersion(InstanceVersion),
+}
+
+/// Turns what the user typed ("mobilizon.fr", "https://mobilizon.fr/events")
+/// into the root url of the instance.
+pub fn parse_instance_url(input: &str) -> Result<Url, url::ParseError> {
+    let input = input.trim();
+    let with_scheme = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("https://{}", input)
+    };
+    let parsed = Url::parse(&with_scheme)?;
+    Url::parse(&parsed.origin().ascii_serialization())
+}
+
+/// The GraphQL endpoint of an instance.
+pub fn api_url(instance_url: &Url) -> Url {
+    instance_url
+        .join("api")
+        .expect("Joining a relative path to an instance root should not fail.")
+}
+
+/// Checks that what the user typed points to an instance we can talk to.
+pub async fn validate_instance(input: &str) -> Result<Url, InstanceValidationError> {
+    let instance_url = parse_instance_url(input).map_err(InstanceValidationError::InvalidUrl)?;
+    check_instance(&MobilizonClient::new(&instance_url)).await?;
+    Ok(instance_url)
+}
+
+/// Fetches the configuration of the instance and only accepts it if its
+/// version is one we know how to talk to.
+pub async fn check_instance(api: &dyn MobilizonApi) -> Result<(), InstanceValidationError> {
+    let config = api
+        .fetch_config()
+        .await
+        .map_err(InstanceValidationError::ConfigFetchError)?;
+    if config.instance_version.is_supported() {
+        Ok(())
+    } else {
+        Err(InstanceValidationError::UnsupportedVersion(
+            config.instance_version,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InstanceValidationError, api_url, check_instance, parse_instance_url};
+    use crate::core::instance_version::InstanceVersion;
+    use crate::infra::fake::FakeInstance;
+
+    #[tokio::test]
+    async fn supported_instance_is_accepted() {
+        let instance = FakeInstance {
+            version: InstanceVersion {
+                major: 5,
+                minor: 1,
+                patch: 0,
+            },
+            ..Default::default()
+        };
+        assert!(check_instance(&instance).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn outdated_instance_is_rejected() {
+        let instance = FakeInstance {
+            version: InstanceVersion {
+                major: 2,
+                minor: 1,
+                patch: 0,
+            },
+            ..Default::default()
+        };
+        assert!(matches!(
+            check_instance(&instance).await,
+            Err(InstanceValidationError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn instance_url_defaults_to_https() {
+        assert_eq!(
+            parse_instance_url("mobilizon.fr").unwrap().as_str(),
+            "https://mobilizon.fr/"
+        );
+    }
+
+    #[test]
+    fn instance_url_keeps_only_the_origin() {
+        assert_eq!(
+            parse_instance_url("  http://localhost:4000/events/abc ")
+                .unwrap()
+                .as_str(),
+            "http://localhost:4000/"
+        );
+    }
+
+    #[test]
+    fn api_url_is_under_instance_root() {
+        let instance = parse_instance_url("https://mobilizon.fr").unwrap();
+        assert_eq!(api_url(&instance).as_str(), "https://mobilizon.fr/api");
+    }
+}
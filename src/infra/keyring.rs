@@ -0,0 +1,86 @@
+use url::Url;
+
+/// Tells apart the items of the application from the others of the keyring.
+const APPLICATION_ATTRIBUTE: (&str, &str) = ("application", "space.soulie.mobilisation");
+
+/// Errors when reading or writing the session in the keyring
+pub enum KeyringError {
+    SecretService(oo7::Error),
+    InvalidSecret(std::string::FromUtf8Error),
+}
+
+/// Keeps the refresh token of the session in the keyring of the user, where
+/// other applications cannot read it. Only one session is kept at a time,
+/// that of the instance in use.
+#[derive(Debug, Clone)]
+pub struct SessionKeyring {
+    instance_url: Url,
+}
+
+impl SessionKeyring {
+    pub fn new(instance_url: &Url) -> Self {
+        Self {
+            instance_url: instance_url.clone(),
+        }
+    }
+
+    /// The refresh token saved for the instance, if any.
+    pub async fn load(&self) -> Result<Option<String>, KeyringError> {
+        let keyring = oo7::Keyring::new()
+            .await
+            .map_err(KeyringError::SecretService)?;
+        let Some(item) = keyring
+            .search_items(&self.attributes())
+            .await
+            .map_err(KeyringError::SecretService)?
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
+        let secret = item.secret().await.map_err(KeyringError::SecretService)?;
+        String::from_utf8(secret.as_bytes().to_vec())
+            .map(Some)
+            .map_err(KeyringError::InvalidSecret)
+    }
+
+    /// Replaces the session saved, even one of another instance.
+    pub async fn save(&self, refresh_token: &str) -> Result<(), KeyringError> {
+        let keyring = oo7::Keyring::new()
+            .await
+            .map_err(KeyringError::SecretService)?;
+        keyring
+            .delete(&vec![APPLICATION_ATTRIBUTE])
+            .await
+            .map_err(KeyringError::SecretService)?;
+        keyring
+            .create_item(
+                &format!(
+                    "Mobilizon session on {}",
+                    self.instance_url.host_str().unwrap_or_default()
+                ),
+                &self.attributes(),
+                oo7::Secret::text(refresh_token),
+                true,
+            )
+            .await
+            .map_err(KeyringError::SecretService)
+    }
+
+    /// Forgets the session saved, whatever its instance.
+    pub async fn clear(&self) -> Result<(), KeyringError> {
+        oo7::Keyring::new()
+            .await
+            .map_err(KeyringError::SecretService)?
+            .delete(&vec![APPLICATION_ATTRIBUTE])
+            .await
+            .map_err(KeyringError::SecretService)
+    }
+
+    fn attributes(&self) -> Vec<(&str, &str)> {
+        vec![
+            APPLICATION_ATTRIBUTE,
+            ("instance", self.instance_url.as_str()),
+        ]
+    }
+}
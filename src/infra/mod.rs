@@ -1,2 +1,13 @@
+pub mod auth;
+pub mod cache;
+pub mod client;
 pub mod config;
+pub mod event_details;
 pub mod events;
+#[cfg(test)]
+pub mod fake;
+pub mod graphql;
+pub mod instance;
+pub mod keyring;
+pub mod participation;
+pub mod pictures;
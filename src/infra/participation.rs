@@ -0,0 +1,357 @@
+use graphql_client::{GraphQLQuery, Response};
+use reqwest;
+use std::str::FromStr;
+use url::Url;
+/// The GraphQL dependency will look for the UUID type in caps
+use uuid::Uuid as UUID;
+
+use crate::core::event::{DateTime as EventDateTime, Event, EventStatus};
+use crate::core::pagination::Pagination;
+use crate::core::participation::{EventParticipation, ParticipantRole, Participation};
+use crate::infra::client::{MobilizonClient, RequestError};
+use crate::infra::graphql::{GraphQLErrors, check_errors, enum_name};
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.json",
+    query_path = "graphql/participation.graphql",
+    response_derives = "Debug"
+)]
+struct ParticipationQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.json",
+    query_path = "graphql/participation.graphql",
+    response_derives = "Debug"
+)]
+struct JoinEventMutation;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.json",
+    query_path = "graphql/participation.graphql",
+    response_derives = "Debug"
+)]
+struct LeaveEventMutation;
+
+type DateTime = chrono::DateTime<chrono::Utc>;
+type Timezone = String;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "graphql/schema.json",
+    query_path = "graphql/participation.graphql",
+    response_derives = "Debug"
+)]
+struct MyEventsQuery;
+
+/// Participations and organized events are fetched together, page after
+/// page, until there are no more of either.
+const MY_EVENTS_PAGE_SIZE: i64 = 50;
+
+/// Errors when looking up, joining or leaving an event
+pub enum ParticipationError {
+    HttpError(Box<reqwest::Error>),
+    MissingField(String),
+    GraphQLErrors(GraphQLErrors),
+    /// The user has to log in again
+    SessionExpired,
+}
+
+impl From<RequestError> for ParticipationError {
+    fn from(err: RequestError) -> Self {
+        match err {
+            RequestError::HttpError(err) => ParticipationError::HttpError(err),
+            RequestError::SessionExpired => ParticipationError::SessionExpired,
+        }
+    }
+}
+
+/// Finds out whether the logged in user takes part in the event.
+pub(super) async fn fetch_participation(
+    client: &MobilizonClient,
+    event_id: &str,
+) -> Result<Participation, ParticipationError> {
+    let response = client
+        .send_graphql::<ParticipationQuery>(participation_query::Variables {
+            event_id: event_id.to_string(),
+        })
+        .await?
+        .json::<Response<participation_query::ResponseData>>()
+        .await
+        .map_err(|e| ParticipationError::HttpError(Box::new(e)))?;
+    let data = check_errors(response)
+        .map_err(|errors| ParticipationError::GraphQLErrors(errors.without_data()))?;
+    let person = data
+        .and_then(|data| data.logged_person)
+        .ok_or(ParticipationError::MissingField("loggedPerson".to_string()))?;
+    let actor_id = person
+        .id
+        .ok_or(ParticipationError::MissingField("id".to_string()))?;
+    let role = person
+        .participations
+        .and_then(|participations| {
+            participations
+                .elements
+                .unwrap_or_default()
+                .into_iter()
+                .flatten()
+                .next()
+        })
+        .and_then(|participation| participation.role)
+        .and_then(|role| decode_role(&role));
+    Ok(Participation { actor_id, role })
+}
+
+/// Returns the role the server gave, which depends on the join options.
+pub(super) async fn join_event(
+    client: &MobilizonClient,
+    event_id: &str,
+    actor_id: &str,
+) -> Result<Option<ParticipantRole>, ParticipationError> {
+    let response = client
+        .send_graphql::<JoinEventMutation>(join_event_mutation::Variables {
+            event_id: event_id.to_string(),
+            actor_id: actor_id.to_string(),
+        })
+        .await?
+        .json::<Response<join_event_mutation::ResponseData>>()
+        .await
+        .map_err(|e| ParticipationError::HttpError(Box::new(e)))?;
+    let data = check_errors(response)
+        .map_err(|errors| ParticipationError::GraphQLErrors(errors.without_data()))?;
+    Ok(data
+        .and_then(|data| data.join_event)
+        .and_then(|participant| participant.role)
+        .and_then(|role| decode_role(&role)))
+}
+
+pub(super) async fn leave_event(
+    client: &MobilizonClient,
+    event_id: &str,
+    actor_id: &str,
+) -> Result<(), ParticipationError> {
+    let response = client
+        .send_graphql::<LeaveEventMutation>(leave_event_mutation::Variables {
+            event_id: event_id.to_string(),
+            actor_id: actor_id.to_string(),
+        })
+        .await?
+        .json::<Response<leave_event_mutation::ResponseData>>()
+        .await
+        .map_err(|e| ParticipationError::HttpError(Box::new(e)))?;
+    check_errors(response)
+        .map(|_| ())
+        .map_err(|errors| ParticipationError::GraphQLErrors(errors.without_data()))
+}
+
+/// One page of a list of the user, along with how many elements the server
+/// sent, some of which may have been left out, and how many there are in all.
+struct ListPage<T> {
+    elements: Vec<T>,
+    received: usize,
+    total: i64,
+}
+
+/// Events the logged in user takes part in, or organizes without having a
+/// participation for them.
+pub(super) async fn fetch_my_events(
+    client: &MobilizonClient,
+) -> Result<Vec<EventParticipation>, ParticipationError> {
+    let mut participation_pages = Pagination::new(MY_EVENTS_PAGE_SIZE);
+    let mut organized_pages = Pagination::new(MY_EVENTS_PAGE_SIZE);
+    let mut participations = Vec::new();
+    let mut organized = Vec::new();
+    while let Some(page) = participation_pages
+        .next_page()
+        .or(organized_pages.next_page())
+    {
+        let (participations_page, organized_page) = fetch_my_events_page(client, page).await?;
+        // The list already exhausted comes back empty
+        if !participation_pages.is_exhausted() {
+            participation_pages
+                .page_loaded(participations_page.received, participations_page.total);
+            participations.extend(participations_page.elements);
+        }
+        if !organized_pages.is_exhausted() {
+            organized_pages.page_loaded(organized_page.received, organized_page.total);
+            organized.extend(organized_page.elements);
+        }
+    }
+    organized.into_iter().for_each(|event| {
+        if !participations.iter().any(|p| p.event.id == event.id) {
+            participations.push(EventParticipation {
+                event,
+                role: ParticipantRole::Creator,
+            });
+        }
+    });
+    Ok(participations)
+}
+
+async fn fetch_my_events_page(
+    client: &MobilizonClient,
+    page: i64,
+) -> Result<(ListPage<EventParticipation>, ListPage<Event>), ParticipationError> {
+    let response = client
+        .send_graphql::<MyEventsQuery>(my_events_query::Variables {
+            page,
+            limit: MY_EVENTS_PAGE_SIZE,
+        })
+        .await?
+        .json::<Response<my_events_query::ResponseData>>()
+        .await
+        .map_err(|e| ParticipationError::HttpError(Box::new(e)))?;
+    let data = check_errors(response)
+        .map_err(|errors| ParticipationError::GraphQLErrors(errors.without_data()))?;
+    decode_my_events_page(data)
+}
+
+fn decode_my_events_page(
+    data: Option<my_events_query::ResponseData>,
+) -> Result<(ListPage<EventParticipation>, ListPage<Event>), ParticipationError> {
+    let person = data
+        .and_then(|data| data.logged_person)
+        .ok_or(ParticipationError::MissingField("loggedPerson".to_string()))?;
+    let participations = person
+        .participations
+        .ok_or(ParticipationError::MissingField(
+            "participations".to_string(),
+        ))?;
+    let elements = participations.elements.unwrap_or_default();
+    let participations = ListPage {
+        received: elements.len(),
+        total: participations.total.unwrap_or_default(),
+        elements: elements
+            .into_iter()
+            .flatten()
+            .filter_map(|participation| {
+                let role = participation.role.as_ref().and_then(decode_role)?;
+                let event = participation.event?;
+                Some(EventParticipation {
+                    event: decode_event(EventFields {
+                        uuid: event.uuid,
+                        title: event.title,
+                        description: event.description,
+                        begins_on: event.begins_on,
+                        ends_on: event.ends_on,
+                        status: event.status.as_ref().and_then(enum_name),
+                        picture_url: event.picture.and_then(|picture| picture.url),
+                        time_zone: event.options.and_then(|options| options.timezone),
+                    })?,
+                    role,
+                })
+            })
+            .collect(),
+    };
+    let organized = person
+        .organized_events
+        .ok_or(ParticipationError::MissingField(
+            "organizedEvents".to_string(),
+        ))?;
+    let elements = organized.elements.unwrap_or_default();
+    let organized = ListPage {
+        received: elements.len(),
+        total: organized.total.unwrap_or_default(),
+        elements: elements
+            .into_iter()
+            .flatten()
+            .filter_map(|event| {
+                decode_event(EventFields {
+                    uuid: event.uuid,
+                    title: event.title,
+                    description: event.description,
+                    begins_on: event.begins_on,
+                    ends_on: event.ends_on,
+                    status: event.status.as_ref().and_then(enum_name),
+                    picture_url: event.picture.and_then(|picture| picture.url),
+                    time_zone: event.options.and_then(|options| options.timezone),
+                })
+            })
+            .collect(),
+    };
+    Ok((participations, organized))
+}
+
+/// What both queries tell about an event, each in its own types.
+struct EventFields {
+    uuid: Option<UUID>,
+    title: Option<String>,
+    description: Option<String>,
+    begins_on: Option<DateTime>,
+    ends_on: Option<DateTime>,
+    status: Option<String>,
+    picture_url: Option<String>,
+    time_zone: Option<String>,
+}
+
+/// Events missing what the timeline needs to show them are left out.
+fn decode_event(fields: EventFields) -> Option<Event> {
+    Some(Event {
+        id: fields.uuid?,
+        title: fields.title?,
+        description: fields.description.filter(|d| !d.is_empty()),
+        picture_url: fields.picture_url.and_then(|url| Url::from_str(&url).ok()),
+        begins_on: EventDateTime::new(fields.begins_on?),
+        ends_on: EventDateTime::new(fields.ends_on?),
+        status: fields
+            .status
+            .and_then(|status| EventStatus::from_str(&status).ok())
+            .unwrap_or_default(),
+        time_zone: fields.time_zone.filter(|zone| !zone.is_empty()),
+    })
+}
+
+/// Each query has its own copy of the enum, they are told apart by name.
+fn decode_role<T: serde::Serialize>(role: &T) -> Option<ParticipantRole> {
+    ParticipantRole::from_str(&enum_name(role)?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_json(uuid: &str, title: &str) -> serde_json::Value {
+        serde_json::json!({
+            "uuid": uuid,
+            "title": title,
+            "description": "",
+            "beginsOn": "2025-01-01T10:00:00Z",
+            "endsOn": "2025-01-01T12:00:00Z",
+            "status": "CONFIRMED",
+            "picture": null,
+            "options": null
+        })
+    }
+
+    #[test]
+    fn pages_of_my_events_tell_how_many_there_are_in_all() {
+        let data = serde_json::from_value::<my_events_query::ResponseData>(serde_json::json!({
+            "loggedPerson": {
+                "participations": {
+                    "total": 120,
+                    "elements": [
+                        {
+                            "role": "PARTICIPANT",
+                            "event": event_json("6a3e2e58-2fb4-4d9b-9c7c-5d0a8c2a4d1e", "Picnic")
+                        },
+                        // Left out, but still sent by the server
+                        { "role": "PARTICIPANT", "event": null }
+                    ]
+                },
+                "organizedEvents": {
+                    "total": 1,
+                    "elements": [event_json("0f8e3c0a-6d3b-4c38-a1b4-0b1f4d0e2a77", "Workshop")]
+                }
+            }
+        }))
+        .unwrap();
+        let (participations, organized) = decode_my_events_page(Some(data)).ok().unwrap();
+        assert_eq!(participations.elements.len(), 1);
+        assert_eq!(participations.received, 2);
+        assert_eq!(participations.total, 120);
+        assert_eq!(organized.elements[0].title, "Workshop");
+        assert_eq!(organized.total, 1);
+    }
+}
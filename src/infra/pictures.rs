@@ -0,0 +1,403 @@
+use bytes::Bytes;
+use gtk::glib;
+use reqwest::StatusCode;
+use reqwest::header::{ETAG, HeaderName, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{Semaphore, oneshot};
+use url::Url;
+
+use crate::infra::cache::write_atomically;
+use crate::infra::client::MobilizonClient;
+use crate::infra::events::EventPictureFetchError;
+
+/// Pictures downloaded at the same time, the others wait for their turn.
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+/// Pictures kept on disk take up this much space at most, the ones used
+/// least recently go first.
+const MAX_DIRECTORY_SIZE: u64 = 200 * 1024 * 1024;
+
+/// Pictures not used for this long are forgotten.
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Pictures written between two looks at the space they take.
+const EVICTION_INTERVAL: usize = 50;
+
+/// Loads waiting for a picture that is already being downloaded.
+type Waiters = HashMap<Url, Vec<oneshot::Sender<Bytes>>>;
+
+/// Downloads the pictures of events a few at a time, and only once when
+/// several widgets ask for the same one. Pictures are kept on disk when given
+/// a directory, and only downloaded again when the server says they changed.
+pub struct PictureLoader {
+    directory: Option<PathBuf>,
+    downloads: Semaphore,
+    in_flight: Mutex<Waiters>,
+    writes: AtomicUsize,
+}
+
+/// What the server told about a picture kept on disk, to ask it later
+/// whether the picture changed.
+#[derive(Serialize, Deserialize)]
+struct Validators {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Forgets about a download once it is over, even when the load is dropped
+/// halfway, so that nobody waits for it forever.
+struct InFlight<'a> {
+    in_flight: &'a Mutex<Waiters>,
+    url: &'a Url,
+}
+
+impl PictureLoader {
+    pub fn new(directory: Option<PathBuf>) -> Self {
+        PictureLoader {
+            directory,
+            downloads: Semaphore::new(MAX_CONCURRENT_DOWNLOADS),
+            in_flight: Mutex::new(HashMap::new()),
+            writes: AtomicUsize::new(0),
+        }
+    }
+
+    pub(super) async fn load(
+        &self,
+        client: &MobilizonClient,
+        url: &Url,
+    ) -> Result<Bytes, EventPictureFetchError> {
+        self.deduplicated(url, || self.download(client, url)).await
+    }
+
+    /// Runs `download` unless the picture is already on its way, in which
+    /// case its bytes are shared. Loads that waited for a download that
+    /// failed give it a try on their own.
+    async fn deduplicated<F, Fut, E>(&self, url: &Url, download: F) -> Result<Bytes, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Bytes, E>>,
+    {
+        let waiting = {
+            let mut in_flight = self
+                .in_flight
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            match in_flight.get_mut(url) {
+                Some(waiters) => {
+                    let (sender, receiver) = oneshot::channel();
+                    waiters.push(sender);
+                    Some(receiver)
+                }
+                None => {
+                    in_flight.insert(url.clone(), Vec::new());
+                    None
+                }
+            }
+        };
+        if let Some(receiver) = waiting {
+            return match receiver.await {
+                Ok(bytes) => Ok(bytes),
+                Err(_) => download().await,
+            };
+        }
+        let guard = InFlight {
+            in_flight: &self.in_flight,
+            url,
+        };
+        let result = download().await;
+        if let Ok(bytes) = &result {
+            guard.take_waiters().into_iter().for_each(|waiter| {
+                let _ = waiter.send(bytes.clone());
+            });
+        }
+        result
+    }
+
+    async fn download(
+        &self,
+        client: &MobilizonClient,
+        url: &Url,
+    ) -> Result<Bytes, EventPictureFetchError> {
+        // The semaphore is never closed
+        let _permit = self.downloads.acquire().await;
+        let stored = self.read(url).await;
+        let mut request = client.get(url);
+        if let Some((validators, _)) = &stored {
+            if let Some(etag) = &validators.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let response = match request.send().await.and_then(|r| r.error_for_status()) {
+            Ok(response) => response,
+            // Better an outdated picture than none when offline
+            Err(err) => {
+                return stored
+                    .map(|(_, bytes)| bytes)
+                    .ok_or(EventPictureFetchError::HttpError(Box::new(err)));
+            }
+        };
+        if response.status() == StatusCode::NOT_MODIFIED
+            && let Some((_, bytes)) = stored
+        {
+            return Ok(bytes);
+        }
+        let validators = Validators {
+            url: url.to_string(),
+            etag: header(&response, ETAG),
+            last_modified: header(&response, LAST_MODIFIED),
+        };
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| EventPictureFetchError::HttpError(Box::new(e)))?;
+        // A picture that could not be stored is downloaded again next time
+        let _ = self.write(url, &validators, &bytes).await;
+        Ok(bytes)
+    }
+
+    async fn read(&self, url: &Url) -> Option<(Validators, Bytes)> {
+        let path = self.directory.as_ref()?.join(picture_key(url));
+        let validators = tokio::fs::read(path.with_extension("json")).await.ok()?;
+        let validators = serde_json::from_slice::<Validators>(&validators).ok()?;
+        // Two urls can end up with the same key, however unlikely
+        if validators.url != url.as_str() {
+            return None;
+        }
+        let bytes = tokio::fs::read(&path).await.ok()?;
+        // Pictures in use are the last ones to be evicted
+        let _ = tokio::task::spawn_blocking(move || {
+            std::fs::File::options()
+                .write(true)
+                .open(path)?
+                .set_modified(SystemTime::now())
+        })
+        .await;
+        Some((validators, Bytes::from(bytes)))
+    }
+
+    async fn write(
+        &self,
+        url: &Url,
+        validators: &Validators,
+        bytes: &Bytes,
+    ) -> std::io::Result<()> {
+        let Some(directory) = &self.directory else {
+            return Ok(());
+        };
+        let path = directory.join(picture_key(url));
+        let validators = serde_json::to_vec(validators)?;
+        write_atomically(&path, bytes).await?;
+        write_atomically(&path.with_extension("json"), &validators).await?;
+        if self
+            .writes
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(EVICTION_INTERVAL)
+        {
+            evict(directory).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Removes the pictures not used for long, then the least recently used
+/// ones until the others fit in `MAX_DIRECTORY_SIZE`.
+async fn evict(directory: &Path) -> std::io::Result<()> {
+    // Size and last use of each picture, its validators included
+    let mut pictures = HashMap::<String, (u64, SystemTime)>::new();
+    let mut entries = tokio::fs::read_dir(directory).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        let path = entry.path();
+        let Some(key) = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+        else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let modified = metadata.modified()?;
+        let picture = pictures.entry(key).or_insert((0, modified));
+        picture.0 += metadata.len();
+        picture.1 = picture.1.max(modified);
+    }
+    for key in unwanted_pictures(pictures, SystemTime::now()) {
+        for path in [
+            directory.join(&key),
+            directory.join(key).with_extension("json"),
+        ] {
+            match tokio::fs::remove_file(path).await {
+                Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Keys of the pictures to evict, given their size and when they were last
+/// used.
+fn unwanted_pictures(pictures: HashMap<String, (u64, SystemTime)>, now: SystemTime) -> Vec<String> {
+    let mut pictures = pictures
+        .into_iter()
+        .collect::<Vec<(String, (u64, SystemTime))>>();
+    // Most recently used first
+    pictures.sort_by(|(_, (_, a)), (_, (_, b))| b.cmp(a));
+    let mut size = 0;
+    pictures
+        .into_iter()
+        .filter(|(_, (length, used))| {
+            size += length;
+            size > MAX_DIRECTORY_SIZE || now.duration_since(*used).is_ok_and(|age| age > MAX_AGE)
+        })
+        .map(|(key, _)| key)
+        .collect()
+}
+
+impl InFlight<'_> {
+    fn take_waiters(&self) -> Vec<oneshot::Sender<Bytes>> {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(self.url)
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        // Dropping the senders wakes up the waiters
+        self.take_waiters();
+    }
+}
+
+/// Name of the file a picture is kept in, the same from one run to the next.
+fn picture_key(url: &Url) -> String {
+    glib::compute_checksum_for_data(glib::ChecksumType::Sha256, url.as_str().as_bytes())
+        .expect("Sha256 checksums are always available")
+        .to_string()
+}
+
+fn header(response: &reqwest::Response, name: HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url() -> Url {
+        Url::parse("https://mobilizon.example/media/picture.jpg").unwrap()
+    }
+
+    #[tokio::test]
+    async fn concurrent_loads_share_one_download() {
+        let loader = PictureLoader::new(None);
+        let downloads = AtomicUsize::new(0);
+        let download = || async {
+            downloads.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok::<_, ()>(Bytes::from_static(b"picture"))
+        };
+        let url = url();
+        let (first, second) = tokio::join!(
+            loader.deduplicated(&url, download),
+            loader.deduplicated(&url, download)
+        );
+        assert_eq!(first, Ok(Bytes::from_static(b"picture")));
+        assert_eq!(second, Ok(Bytes::from_static(b"picture")));
+        assert_eq!(downloads.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn waiting_loads_try_again_when_the_download_fails() {
+        let loader = PictureLoader::new(None);
+        let url = url();
+        let (first, second) = tokio::join!(
+            loader.deduplicated(&url, || async {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                Err::<Bytes, _>(())
+            }),
+            loader.deduplicated(&url, || async {
+                Ok::<_, ()>(Bytes::from_static(b"picture"))
+            })
+        );
+        assert_eq!(first, Err(()));
+        assert_eq!(second, Ok(Bytes::from_static(b"picture")));
+    }
+
+    #[tokio::test]
+    async fn dropped_loads_are_forgotten() {
+        let loader = PictureLoader::new(None);
+        let url = url();
+        let timed_out = tokio::time::timeout(
+            Duration::from_millis(1),
+            loader.deduplicated(&url, std::future::pending::<Result<Bytes, ()>>),
+        )
+        .await;
+        assert!(timed_out.is_err());
+        assert!(loader.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn pictures_are_kept_on_disk() {
+        let directory =
+            std::env::temp_dir().join(format!("mobilisation-pictures-{}", std::process::id()));
+        let loader = PictureLoader::new(Some(directory.clone()));
+        let url = url();
+        let validators = Validators {
+            url: url.to_string(),
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+        };
+        assert!(
+            loader
+                .write(&url, &validators, &Bytes::from_static(b"picture"))
+                .await
+                .is_ok()
+        );
+        let (validators, bytes) = loader.read(&url).await.unwrap();
+        assert_eq!(validators.etag.as_deref(), Some("\"abc\""));
+        assert_eq!(bytes, Bytes::from_static(b"picture"));
+        let _ = std::fs::remove_dir_all(directory);
+    }
+
+    #[test]
+    fn picture_keys_do_not_change() {
+        assert_eq!(
+            picture_key(&url()),
+            "b7be3925c7b9fcec7eeec4f3b66cd1341af1776efe13a949679a746129e9d078"
+        );
+    }
+
+    #[test]
+    fn old_and_least_recently_used_pictures_are_evicted() {
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        let pictures = HashMap::from([
+            ("recent".to_string(), (MAX_DIRECTORY_SIZE / 2, now)),
+            ("yesterday".to_string(), (MAX_DIRECTORY_SIZE / 2, now - day)),
+            ("last week".to_string(), (1, now - 7 * day)),
+            ("last year".to_string(), (1, now - 365 * day)),
+        ]);
+        let mut unwanted = unwanted_pictures(pictures, now);
+        unwanted.sort();
+        assert_eq!(unwanted, vec!["last week", "last year"]);
+    }
+}
@@ -0,0 +1,146 @@
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::glib;
+use gtk::glib::subclass::Signal;
+use std::sync::OnceLock;
+
+use crate::infra::instance::validate_instance;
+use crate::{runtime, settings};
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[template(resource = "/space/soulie/mobilisation/instance_picker.ui")]
+    pub struct MobilisationInstancePicker {
+        #[template_child]
+        pub url_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub error_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub spinner: TemplateChild<adw::Spinner>,
+        #[template_child]
+        pub connect_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MobilisationInstancePicker {
+        const NAME: &'static str = "MobilisationInstancePicker";
+        type Type = super::MobilisationInstancePicker;
+        type ParentType = gtk::Box;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MobilisationInstancePicker {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("instance-validated")
+                        .param_types([String::static_type()])
+                        .build(),
+                ]
+            })
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+            self.url_row.set_text(&settings().string("instance-url"));
+            self.connect_button.connect_clicked(glib::clone!(
+                #[weak]
+                obj,
+                move |_| obj.validate()
+            ));
+            self.url_row.connect_entry_activated(glib::clone!(
+                #[weak]
+                obj,
+                move |_| obj.validate()
+            ));
+        }
+    }
+    impl WidgetImpl for MobilisationInstancePicker {}
+    impl BoxImpl for MobilisationInstancePicker {}
+}
+
+glib::wrapper! {
+    pub struct MobilisationInstancePicker(ObjectSubclass<imp::MobilisationInstancePicker>)
+    @extends gtk::Box, gtk::Widget,
+    @implements gtk::Buildable;
+}
+
+impl MobilisationInstancePicker {
+    /// Called once the instance has been checked and saved in the settings.
+    pub fn connect_instance_validated<F: Fn(&Self, String) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "instance-validated",
+            false,
+            glib::closure_local!(move |picker: &Self, instance_url: String| {
+                f(picker, instance_url)
+            }),
+        )
+    }
+
+    fn set_busy(&self, busy: bool) {
+        self.imp().spinner.set_visible(busy);
+        self.imp().connect_button.set_sensitive(!busy);
+        self.imp().url_row.set_sensitive(!busy);
+    }
+
+    fn validate(&self) {
+        let input = self.imp().url_row.text().to_string();
+        self.imp().error_label.set_visible(false);
+        self.set_busy(true);
+        let (sender, receiver) = async_channel::unbounded();
+        runtime().spawn(async move {
+            let _ = sender.send(validate_instance(&input).await).await;
+        });
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let received = receiver.recv().await;
+                obj.set_busy(false);
+                let _ = received
+                    .map(|value| match value {
+                        Ok(instance_url) => {
+                            let instance_url = instance_url.to_string();
+                            obj.imp().url_row.set_text(&instance_url);
+                            let _ = settings()
+                                .set_string("instance-url", &instance_url)
+                                .map_err(|err| {
+                                    glib::g_log!(
+                                        glib::LogLevel::Warning,
+                                        "Error saving instance : {}",
+                                        err
+                                    );
+                                });
+                            obj.emit_by_name::<()>("instance-validated", &[&instance_url]);
+                        }
+                        Err(err) => {
+                            glib::g_log!(
+                                glib::LogLevel::Warning,
+                                "Error validating instance : {}",
+                                err
+                            );
+                            obj.imp().error_label.set_label(&err.to_string());
+                            obj.imp().error_label.set_visible(true);
+                        }
+                    })
+                    .map_err(|err| {
+                        glib::g_log!(glib::LogLevel::Error, "Channel error : {}", err);
+                    });
+            }
+        ));
+    }
+}
@@ -0,0 +1,135 @@
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::glib;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use crate::infra::client::MobilizonApi;
+use crate::{runtime, settings};
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[template(resource = "/space/soulie/mobilisation/login_dialog.ui")]
+    pub struct MobilisationLoginDialog {
+        #[template_child]
+        pub email_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub password_row: TemplateChild<adw::PasswordEntryRow>,
+        #[template_child]
+        pub error_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub spinner: TemplateChild<adw::Spinner>,
+        #[template_child]
+        pub login_button: TemplateChild<gtk::Button>,
+        pub api: RefCell<Option<Arc<dyn MobilizonApi>>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MobilisationLoginDialog {
+        const NAME: &'static str = "MobilisationLoginDialog";
+        type Type = super::MobilisationLoginDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MobilisationLoginDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+            self.email_row.set_text(&settings().string("account-email"));
+            self.login_button.connect_clicked(glib::clone!(
+                #[weak]
+                obj,
+                move |_| obj.login()
+            ));
+            self.password_row.connect_entry_activated(glib::clone!(
+                #[weak]
+                obj,
+                move |_| obj.login()
+            ));
+        }
+    }
+    impl WidgetImpl for MobilisationLoginDialog {}
+    impl AdwDialogImpl for MobilisationLoginDialog {}
+}
+
+glib::wrapper! {
+    pub struct MobilisationLoginDialog(ObjectSubclass<imp::MobilisationLoginDialog>)
+    @extends adw::Dialog, gtk::Widget,
+    @implements gtk::Buildable;
+}
+
+impl MobilisationLoginDialog {
+    pub fn new(api: Arc<dyn MobilizonApi>) -> Self {
+        let dialog = glib::Object::builder::<MobilisationLoginDialog>().build();
+        dialog.imp().api.replace(Some(api));
+        dialog
+    }
+
+    fn set_busy(&self, busy: bool) {
+        self.imp().spinner.set_visible(busy);
+        self.imp().login_button.set_sensitive(!busy);
+        self.imp().email_row.set_sensitive(!busy);
+        self.imp().password_row.set_sensitive(!busy);
+    }
+
+    /// The session itself is saved by the window, which is told about it by
+    /// the client.
+    fn login(&self) {
+        let Some(api) = self.imp().api.borrow().clone() else {
+            return;
+        };
+        let email = self.imp().email_row.text().trim().to_string();
+        let password = self.imp().password_row.text().to_string();
+        self.imp().error_label.set_visible(false);
+        self.set_busy(true);
+        let (sender, receiver) = async_channel::unbounded();
+        runtime().spawn(glib::clone!(
+            #[strong]
+            email,
+            async move {
+                let _ = sender.send(api.login(&email, &password).await).await;
+            }
+        ));
+        glib::spawn_future_local(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let received = receiver.recv().await;
+                obj.set_busy(false);
+                let _ = received
+                    .map(|value| match value {
+                        Ok(()) => {
+                            let _ = settings()
+                                .set_string("account-email", &email)
+                                .map_err(|err| {
+                                    glib::g_log!(
+                                        glib::LogLevel::Warning,
+                                        "Error saving account : {}",
+                                        err
+                                    );
+                                });
+                            obj.close();
+                        }
+                        Err(err) => {
+                            glib::g_log!(glib::LogLevel::Warning, "Error logging in : {}", err);
+                            obj.imp().error_label.set_label(&err.to_string());
+                            obj.imp().error_label.set_visible(true);
+                        }
+                    })
+                    .map_err(|err| {
+                        glib::g_log!(glib::LogLevel::Error, "Channel error : {}", err);
+                    });
+            }
+        ));
+    }
+}
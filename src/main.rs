@@ -19,7 +19,10 @@
  */
 
 mod application;
+mod calendar_export;
+mod clock;
 mod config;
+mod description_view;
 mod presentation;
 mod sidebar;
 mod sidebar_header;
@@ -29,6 +32,12 @@ mod infra;
 mod core;
 mod event_preview_model;
 mod event_preview;
+mod event_page;
+mod filter_chip;
+mod instance_picker;
+mod login_dialog;
+mod pictures;
+mod preferences_dialog;
 
 use self::application::MobilisationApplication;
 use self::window::MobilisationWindow;
@@ -45,9 +54,8 @@ fn runtime() -> &'static Runtime {
     RUNTIME.get_or_init(|| Runtime::new().expect("Failed to start tokio runtime"))
 }
 
-fn http_client() -> &'static reqwest::Client {
-    static HTTP_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
-    HTTP_CLIENT.get_or_init(|| reqwest::Client::new())
+fn settings() -> gio::Settings {
+    gio::Settings::new("space.soulie.mobilisation")
 }
 
 fn main() -> glib::ExitCode {
@@ -0,0 +1,51 @@
+use gtk::gdk_pixbuf::PixbufLoader;
+use gtk::prelude::*;
+use gtk::{gdk, gdk_pixbuf, glib};
+
+/// How much room a picture is given where it is shown, in physical pixels.
+#[derive(Debug, Clone, Copy)]
+pub enum PictureFit {
+    /// The picture covers a tile, so its shorter side is the one that fits
+    Cover(i32),
+    /// The picture is no wider than this, keeping its proportions
+    Width(i32),
+}
+
+impl PictureFit {
+    /// How much a picture of `width` by `height` is scaled down to fit.
+    fn scale(self, width: i32, height: i32) -> f64 {
+        match self {
+            PictureFit::Cover(size) => f64::from(size) / f64::from(width.min(height)),
+            PictureFit::Width(max_width) => f64::from(max_width) / f64::from(width),
+        }
+    }
+}
+
+/// Decodes a picture at the size it is shown at, so that large originals
+/// are not kept in memory. WebP and AVIF need the gdk-pixbuf loaders the
+/// Flatpak bundles, elsewhere those of the system.
+pub fn decode_picture(bytes: &glib::Bytes, fit: PictureFit) -> Result<gdk::Texture, glib::Error> {
+    let loader = PixbufLoader::new();
+    loader.connect_size_prepared(move |loader, width, height| {
+        let scale = fit.scale(width, height);
+        if scale < 1.0 {
+            loader.set_size(
+                ((f64::from(width) * scale).round() as i32).max(1),
+                ((f64::from(height) * scale).round() as i32).max(1),
+            );
+        }
+    });
+    let written = loader.write_bytes(bytes);
+    // Closed in any case, gdk-pixbuf complains about loaders left open
+    let closed = loader.close();
+    written.and(closed)?;
+    loader
+        .pixbuf()
+        .map(|pixbuf| gdk::Texture::for_pixbuf(&pixbuf))
+        .ok_or_else(|| {
+            glib::Error::new(
+                gdk_pixbuf::PixbufError::CorruptImage,
+                "The picture has no content",
+            )
+        })
+}
@@ -0,0 +1,61 @@
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+use gtk::glib;
+
+use crate::instance_picker::MobilisationInstancePicker;
+
+mod imp {
+    use super::*;
+
+    #[derive(Default, gtk::CompositeTemplate)]
+    #[template(resource = "/space/soulie/mobilisation/preferences_dialog.ui")]
+    pub struct MobilisationPreferencesDialog {
+        #[template_child]
+        pub instance_picker: TemplateChild<MobilisationInstancePicker>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for MobilisationPreferencesDialog {
+        const NAME: &'static str = "MobilisationPreferencesDialog";
+        type Type = super::MobilisationPreferencesDialog;
+        type ParentType = adw::PreferencesDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for MobilisationPreferencesDialog {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+            self.instance_picker
+                .connect_instance_validated(glib::clone!(
+                    #[weak]
+                    obj,
+                    move |_, _| {
+                        obj.close();
+                    }
+                ));
+        }
+    }
+    impl WidgetImpl for MobilisationPreferencesDialog {}
+    impl AdwDialogImpl for MobilisationPreferencesDialog {}
+    impl PreferencesDialogImpl for MobilisationPreferencesDialog {}
+}
+
+glib::wrapper! {
+    pub struct MobilisationPreferencesDialog(ObjectSubclass<imp::MobilisationPreferencesDialog>)
+    @extends adw::PreferencesDialog, adw::Dialog, gtk::Widget,
+    @implements gtk::Buildable;
+}
+
+impl MobilisationPreferencesDialog {
+    pub fn new() -> Self {
+        glib::Object::builder::<MobilisationPreferencesDialog>().build()
+    }
+}
@@ -0,0 +1,359 @@
+use chrono::{FixedOffset, Offset, TimeZone};
+use gettextrs::{gettext, ngettext};
+use gtk::glib;
+
+use crate::core::event::{
+    DatePrecision, DateTime, Event, HumanReadableDateTime, minutes_rounded_up,
+};
+
+/// Where an event stands in time, as shown on its card.
+pub fn human_readable_label(date_time: &HumanReadableDateTime) -> String {
+    match date_time {
+        HumanReadableDateTime::Ended => gettext("Ended"),
+        HumanReadableDateTime::Ongoing { remaining } => {
+            let minutes = minutes_rounded_up(*remaining);
+            if minutes < 60 {
+                ngettext("Ends in {} minute", "Ends in {} minutes", minutes as u32)
+                    .replace("{}", &minutes.to_string())
+            } else {
+                // Rounded up like the minutes, 61 of them ending within 2 hours
+                let hours = (minutes + 59) / 60;
+                ngettext("Ends in {} hour", "Ends in {} hours", hours as u32)
+                    .replace("{}", &hours.to_string())
+            }
+        }
+        HumanReadableDateTime::OngoingDay { day, days } => gettext("Day {day} of {days}")
+            .replace("{day}", &day.to_string())
+            .replace("{days}", &days.to_string()),
+        HumanReadableDateTime::StartingIn { minutes: 0 } => gettext("Starting now"),
+        HumanReadableDateTime::StartingIn { minutes } => {
+            ngettext("In {} minute", "In {} minutes", *minutes as u32)
+                .replace("{}", &minutes.to_string())
+        }
+        HumanReadableDateTime::Tomorrow(date_time) => gettext("Tomorrow at {time}")
+            .replace("{time}", &format_date(date_time, DatePrecision::Time)),
+        HumanReadableDateTime::Later(precision, date_time) => format_date(date_time, *precision),
+    }
+}
+
+/// When an event takes place in local time, then in the time zone of the
+/// event when clocks there read differently, as for online events followed
+/// from abroad.
+pub fn event_dates_label(event: &Event) -> String {
+    event_dates_label_in(event, &glib::TimeZone::local())
+}
+
+fn event_dates_label_in(event: &Event, user_zone: &glib::TimeZone) -> String {
+    let in_user_zone = |date_time: &DateTime| in_time_zone(user_zone, &date_time.to_utc());
+    let (begins_on, ends_on) = (in_user_zone(&event.begins_on), in_user_zone(&event.ends_on));
+    let user_label = dates_label(event, &begins_on, &ends_on);
+    let Some((name, event_zone)) = event
+        .time_zone
+        .as_deref()
+        .and_then(|name| Some((name, glib::TimeZone::from_identifier(Some(name))?)))
+    else {
+        return user_label;
+    };
+    let in_event_zone = |date_time: &DateTime| in_time_zone(&event_zone, &date_time.to_utc());
+    let (event_begins_on, event_ends_on) = (
+        in_event_zone(&event.begins_on),
+        in_event_zone(&event.ends_on),
+    );
+    if event_begins_on.offset() == begins_on.offset() && event_ends_on.offset() == ends_on.offset()
+    {
+        return user_label;
+    }
+    // Translators: when an event takes place for the user, then where it
+    // takes place, in a time zone named like "America/New York"
+    gettext("{local}\n{event} ({zone})")
+        .replace("{local}", &user_label)
+        .replace(
+            "{event}",
+            &dates_label(event, &event_begins_on, &event_ends_on),
+        )
+        .replace("{zone}", &name.replace('_', " "))
+}
+
+fn dates_label(
+    event: &Event,
+    begins_on: &chrono::DateTime<FixedOffset>,
+    ends_on: &chrono::DateTime<FixedOffset>,
+) -> String {
+    let begins_on = format_date_and_time(begins_on);
+    if event.is_long() {
+        let ends_on = format_date_and_time(ends_on);
+        // Translators: first and last day of an event lasting several days
+        gettext("{start} to {end}")
+            .replace("{start}", &begins_on)
+            .replace("{end}", &ends_on)
+    } else {
+        // Translators: beginning of an event, then how many hours it lasts
+        gettext("{start} - {hours}h")
+            .replace("{start}", &begins_on)
+            .replace("{hours}", &event.compute_duration_in_hours().to_string())
+    }
+}
+
+/// How far from UTC the time zone named `identifier` in the IANA database
+/// is at `date_time`, if the system knows that zone.
+pub fn time_zone_offset(
+    identifier: &str,
+    date_time: &chrono::DateTime<chrono::Utc>,
+) -> Option<FixedOffset> {
+    let zone = glib::TimeZone::from_identifier(Some(identifier))?;
+    Some(*in_time_zone(&zone, date_time).offset())
+}
+
+/// GLib reads the time zone database of the system, which chrono cannot.
+fn in_time_zone(
+    zone: &glib::TimeZone,
+    date_time: &chrono::DateTime<chrono::Utc>,
+) -> chrono::DateTime<FixedOffset> {
+    let interval = zone.find_interval(glib::TimeType::Universal, date_time.timestamp());
+    // Zones without any interval, such as UTC, have no offset
+    let seconds = if interval < 0 {
+        0
+    } else {
+        zone.offset(interval)
+    };
+    date_time.with_timezone(&FixedOffset::east_opt(seconds).unwrap_or(chrono::Utc.fix()))
+}
+
+/// Writes `date_time` in the language and conventions of the user, with
+/// the names of days and months of their locale.
+pub fn format_date<Tz: TimeZone>(
+    date_time: &chrono::DateTime<Tz>,
+    precision: DatePrecision,
+) -> String {
+    let format = match precision {
+        // Translators: time of an event happening today, see
+        // https://docs.gtk.org/glib/method.DateTime.format.html
+        DatePrecision::Time => gettext("%R"),
+        // Translators: day of an event happening this week, see
+        // https://docs.gtk.org/glib/method.DateTime.format.html
+        DatePrecision::Weekday => gettext("%A"),
+        // Translators: date of an event happening this month, see
+        // https://docs.gtk.org/glib/method.DateTime.format.html
+        DatePrecision::WeekdayAndDay => gettext("%A %-e"),
+        // Translators: date of an event happening this year, see
+        // https://docs.gtk.org/glib/method.DateTime.format.html
+        DatePrecision::MonthAndDay => {
+            let format = gettext("%B %-e");
+            // Untranslated, the day and month come in the order of the locale
+            if format == "%B %-e" && day_comes_first() {
+                "%-e %B".to_string()
+            } else {
+                format
+            }
+        }
+        // Translators: date of an event happening next year or later, see
+        // https://docs.gtk.org/glib/method.DateTime.format.html
+        DatePrecision::MonthDayAndYear => gettext("%x"),
+    };
+    format_with(date_time, &format)
+}
+
+/// Whether the locale writes the day before the month, as its numeric
+/// dates tell.
+fn day_comes_first() -> bool {
+    let written = format_with(
+        &chrono::Utc.with_ymd_and_hms(1999, 12, 31, 0, 0, 0).unwrap(),
+        "%x",
+    );
+    matches!(
+        (written.find("31"), written.find("12")),
+        (Some(day), Some(month)) if day < month
+    )
+}
+
+fn format_date_and_time<Tz: TimeZone>(date_time: &chrono::DateTime<Tz>) -> String {
+    // Translators: date and time of the beginning or end of an event, see
+    // https://docs.gtk.org/glib/method.DateTime.format.html
+    format_with(date_time, &gettext("%x %R"))
+}
+
+/// GLib knows the names of days and months in every locale, chrono only in
+/// English.
+fn format_with<Tz: TimeZone>(date_time: &chrono::DateTime<Tz>, format: &str) -> String {
+    let offset = date_time.offset().fix().local_minus_utc();
+    glib::DateTime::from_unix_utc(date_time.timestamp())
+        .and_then(|utc| utc.to_timezone(&glib::TimeZone::from_offset(offset)))
+        .and_then(|local| local.format(format))
+        .map(String::from)
+        // GLib only refuses years it cannot represent, past 9999
+        .unwrap_or_else(|_| date_time.naive_local().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gettextrs::{LocaleCategory, setlocale};
+
+    fn date_time(rfc3339: &str) -> chrono::DateTime<chrono::FixedOffset> {
+        chrono::DateTime::parse_from_rfc3339(rfc3339).unwrap()
+    }
+
+    /// Set in the process running the locale test on its own.
+    const LOCALE_TEST_VARIABLE: &str = "MOBILISATION_LOCALE_TEST";
+
+    /// The locale belongs to the whole process, so the test runs again in a
+    /// process of its own where every locale is tried in turn. Patterns are
+    /// not translated there, the order of the locale is followed all the same.
+    #[test]
+    fn dates_are_written_in_the_locale_of_the_user() {
+        if std::env::var_os(LOCALE_TEST_VARIABLE).is_none() {
+            let (_, test_path) = module_path!().split_once("::").unwrap();
+            let status = std::process::Command::new(std::env::current_exe().unwrap())
+                .args([
+                    &format!("{}::dates_are_written_in_the_locale_of_the_user", test_path),
+                    "--exact",
+                ])
+                .env(LOCALE_TEST_VARIABLE, "1")
+                .status()
+                .unwrap();
+            assert!(status.success());
+            return;
+        }
+        let date_time = date_time("2001-02-05T14:30:00+01:00");
+        let locales = [
+            (
+                "C",
+                ["14:30", "Monday", "Monday 5", "February 5", "02/05/01"],
+            ),
+            (
+                "en_US.UTF-8",
+                ["14:30", "Monday", "Monday 5", "February 5", "02/05/2001"],
+            ),
+            (
+                "fr_FR.UTF-8",
+                ["14:30", "lundi", "lundi 5", "5 février", "05/02/2001"],
+            ),
+            (
+                "de_DE.UTF-8",
+                ["14:30", "Montag", "Montag 5", "5 Februar", "05.02.2001"],
+            ),
+        ];
+        let precisions = [
+            DatePrecision::Time,
+            DatePrecision::Weekday,
+            DatePrecision::WeekdayAndDay,
+            DatePrecision::MonthAndDay,
+            DatePrecision::MonthDayAndYear,
+        ];
+        locales.into_iter().for_each(|(locale, expected)| {
+            // Systems without the locale cannot be tested in it
+            if setlocale(LocaleCategory::LcAll, locale).is_none() {
+                return;
+            }
+            precisions
+                .into_iter()
+                .zip(expected)
+                .for_each(|(precision, expected)| {
+                    assert_eq!(format_date(&date_time, precision), expected, "{}", locale);
+                });
+        });
+    }
+
+    #[test]
+    fn dates_are_written_in_their_own_time_zone() {
+        let date_time = date_time("2001-02-05T23:30:00-05:00");
+        assert_eq!(format_with(&date_time, "%F %R"), "2001-02-05 23:30");
+        assert_eq!(
+            format_with(&date_time.with_timezone(&chrono::Utc), "%F %R"),
+            "2001-02-06 04:30"
+        );
+    }
+
+    #[test]
+    fn offsets_follow_the_clocks_of_the_zone() {
+        // Systems without the time zone database cannot be tested
+        if glib::TimeZone::from_identifier(Some("America/New_York")).is_none() {
+            return;
+        }
+        let offset = |zone, date_time: &str| {
+            time_zone_offset(zone, &date_time.parse().unwrap()).map(|o| o.local_minus_utc())
+        };
+        assert_eq!(
+            offset("America/New_York", "2025-01-15T12:00:00Z"),
+            Some(-5 * 3600)
+        );
+        assert_eq!(
+            offset("America/New_York", "2025-07-15T12:00:00Z"),
+            Some(-4 * 3600)
+        );
+        assert_eq!(offset("UTC", "2025-07-15T12:00:00Z"), Some(0));
+        assert_eq!(offset("Atlantis/Poseidonia", "2025-07-15T12:00:00Z"), None);
+    }
+
+    #[test]
+    fn event_time_zones_are_shown_when_clocks_differ() {
+        let Some(paris) = glib::TimeZone::from_identifier(Some("Europe/Paris")) else {
+            return;
+        };
+        let event = |time_zone: Option<&str>| Event {
+            begins_on: DateTime::new("2025-03-08T13:30:00Z".parse().unwrap()),
+            ends_on: DateTime::new("2025-03-08T17:00:00Z".parse().unwrap()),
+            time_zone: time_zone.map(str::to_string),
+            ..Default::default()
+        };
+        let label = event_dates_label_in(&event(Some("America/New_York")), &paris);
+        assert_eq!(label.lines().count(), 2);
+        assert!(label.ends_with(" (America/New York)"));
+        ["Europe/Berlin", "Europe/Paris", "Atlantis/Poseidonia"]
+            .into_iter()
+            .map(Some)
+            .chain([None])
+            .for_each(|time_zone| {
+                assert_eq!(
+                    event_dates_label_in(&event(time_zone), &paris),
+                    event_dates_label_in(&event(None), &paris)
+                );
+            });
+    }
+
+    #[test]
+    fn close_events_are_told_relative_to_now() {
+        let labels = [
+            (HumanReadableDateTime::Ended, "Ended"),
+            (
+                HumanReadableDateTime::Ongoing {
+                    remaining: chrono::TimeDelta::minutes(150),
+                },
+                "Ends in 3 hours",
+            ),
+            (
+                HumanReadableDateTime::Ongoing {
+                    remaining: chrono::TimeDelta::minutes(119),
+                },
+                "Ends in 2 hours",
+            ),
+            (
+                HumanReadableDateTime::Ongoing {
+                    remaining: chrono::TimeDelta::minutes(60),
+                },
+                "Ends in 1 hour",
+            ),
+            (
+                HumanReadableDateTime::Ongoing {
+                    remaining: chrono::TimeDelta::seconds(30),
+                },
+                "Ends in 1 minute",
+            ),
+            (
+                HumanReadableDateTime::OngoingDay { day: 2, days: 3 },
+                "Day 2 of 3",
+            ),
+            (
+                HumanReadableDateTime::StartingIn { minutes: 0 },
+                "Starting now",
+            ),
+            (
+                HumanReadableDateTime::StartingIn { minutes: 20 },
+                "In 20 minutes",
+            ),
+        ];
+        labels.into_iter().for_each(|(date_time, label)| {
+            assert_eq!(human_readable_label(&date_time), label);
+        });
+    }
+}
@@ -1,8 +1,17 @@
 use std::fmt::Display;
 
-use crate::core::instance_version::InstanceVersionParsingError;
+use crate::core::instance_version::{
+    InstanceVersion, InstanceVersionParsingError, MINIMUM_SUPPORTED_VERSION,
+};
+use crate::infra::auth::AuthError;
+use crate::infra::cache::CacheError;
 use crate::infra::config::ConfigFetchError;
-use crate::infra::events::{EventDecodeError, EventsFetchError};
+use crate::infra::event_details::EventDetailsFetchError;
+use crate::infra::events::{EventDecodeError, EventPictureFetchError, EventsFetchError};
+use crate::infra::graphql::GraphQLErrors;
+use crate::infra::instance::InstanceValidationError;
+use crate::infra::keyring::KeyringError;
+use crate::infra::participation::ParticipationError;
 
 impl Display for ConfigFetchError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -14,6 +23,8 @@ impl Display for ConfigFetchError {
             ConfigFetchError::InstanceVersionParsingError(err) => {
                 write!(f, "Instance version could not be parsed: {}", err)
             }
+            ConfigFetchError::GraphQLErrors(errors) => write!(f, "{}", errors),
+            ConfigFetchError::SessionExpired => write!(f, "The session expired."),
         }
     }
 }
@@ -35,6 +46,30 @@ impl Display for InstanceVersionParsingError {
     }
 }
 
+impl Display for InstanceVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl Display for InstanceValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstanceValidationError::InvalidUrl(err) => {
+                write!(f, "This is not a valid address : {}", err)
+            }
+            InstanceValidationError::ConfigFetchError(err) => {
+                write!(f, "Could not reach a Mobilizon instance : {}", err)
+            }
+            InstanceValidationError::UnsupportedVersion(version) => write!(
+                f,
+                "Mobilizon {} is not supported, at least {} is required.",
+                version, MINIMUM_SUPPORTED_VERSION
+            ),
+        }
+    }
+}
+
 impl Display for EventsFetchError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -43,7 +78,109 @@ impl Display for EventsFetchError {
             EventsFetchError::MissingField(name) => {
                 write!(f, "The field {} is missing from the json.", name)
             }
+            EventsFetchError::GraphQLErrors(errors) => write!(f, "{}", errors),
+            EventsFetchError::SessionExpired => write!(f, "The session expired."),
+        }
+    }
+}
+
+impl Display for EventPictureFetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventPictureFetchError::HttpError(err) => write!(f, "Http error : {}", err),
+        }
+    }
+}
+
+impl Display for EventDetailsFetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventDetailsFetchError::HttpError(err) => write!(f, "Http error : {}", err),
+            EventDetailsFetchError::MissingField(name) => {
+                write!(f, "The field {} is missing from the json.", name)
+            }
+            EventDetailsFetchError::NotFound(id) => write!(f, "No event with id {}.", id),
+            EventDetailsFetchError::GraphQLErrors(errors) => write!(f, "{}", errors),
+            EventDetailsFetchError::SessionExpired => write!(f, "The session expired."),
+        }
+    }
+}
+
+impl Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::HttpError(err) => write!(f, "Http error : {}", err),
+            AuthError::MissingField(name) => {
+                write!(f, "The field {} is missing from the json.", name)
+            }
+            AuthError::GraphQLErrors(errors) => write!(f, "{}", errors),
+        }
+    }
+}
+
+impl Display for ParticipationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParticipationError::HttpError(err) => write!(f, "Http error : {}", err),
+            ParticipationError::MissingField(name) => {
+                write!(f, "The field {} is missing from the json.", name)
+            }
+            ParticipationError::GraphQLErrors(errors) => write!(f, "{}", errors),
+            ParticipationError::SessionExpired => write!(f, "The session expired."),
+        }
+    }
+}
+
+impl Display for KeyringError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyringError::SecretService(err) => write!(f, "Secret service error : {}", err),
+            KeyringError::InvalidSecret(err) => write!(f, "Invalid secret : {}", err),
+        }
+    }
+}
+
+impl Display for CacheError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheError::Io(err) => write!(f, "Io error : {}", err),
+            CacheError::Json(err) => write!(f, "Invalid cache entry : {}", err),
+        }
+    }
+}
+
+impl<T> Display for GraphQLErrors<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.partial_data.is_some() {
+            write!(f, "The server only partially answered the request :")?;
+        } else {
+            write!(f, "The server could not answer the request :")?;
+        }
+        for error in &self.errors {
+            write!(f, "\n{}", error.message)?;
+            if let Some(path) = error.path.as_ref().filter(|path| !path.is_empty()) {
+                let path = path
+                    .iter()
+                    .map(|fragment| fragment.to_string())
+                    .collect::<Vec<String>>()
+                    .join("/");
+                write!(f, " (at {})", path)?;
+            }
+            if let Some(extensions) = error
+                .extensions
+                .as_ref()
+                .filter(|extensions| !extensions.is_empty())
+            {
+                // Sorted so that the same error always reads the same
+                let mut extensions = extensions
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect::<Vec<String>>();
+                extensions.sort();
+                write!(f, " [{}]", extensions.join(", "))?;
+            }
         }
+        Ok(())
     }
 }
 
@@ -52,6 +189,7 @@ impl Display for EventDecodeError {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
       EventDecodeError::EventWithNoId => write!(f, "Event with no id."),
+      EventDecodeError::MissingEvent => write!(f, "Event missing from the page."),
       EventDecodeError::InvalidPictureUrl(url) => write!(f, "Invalid picture url {}.", url),
       EventDecodeError::UnexpectedStructureOfEvent(id, debug) => write!(f, "Unexpected structure of event with id: {}\nEvent:\n{}\n", id, debug)
     }
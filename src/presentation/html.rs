@@ -0,0 +1,703 @@
+use url::Url;
+
+/// Longest excerpt of a description, in characters. Cards only show two
+/// lines of it anyway.
+const EXCERPT_LENGTH: usize = 200;
+
+/// Elements dropped along with everything they hold, since what they hold
+/// is not meant to be read as text.
+const DROPPED_ELEMENTS: [&str; 13] = [
+    "script", "style", "iframe", "object", "embed", "template", "noscript", "head", "title", "svg",
+    "math", "select", "textarea",
+];
+
+/// A paragraph, heading or item of a list of a description.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub content: Vec<Inline>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockKind {
+    Paragraph,
+    /// From 1 for the most important headings to 6
+    Heading(u8),
+    /// `depth` is 1 for items of lists that are not in another list, and
+    /// `marker` is either a bullet or the number of the item.
+    ListItem {
+        depth: usize,
+        marker: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inline {
+    Text { text: String, style: Style },
+    Image { url: Url, alt: String },
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub link: Option<Url>,
+}
+
+/// A piece of HTML as found in the source.
+#[derive(Debug, PartialEq)]
+enum Token {
+    Text(String),
+    Start {
+        name: String,
+        attributes: Vec<(String, String)>,
+    },
+    End(String),
+}
+
+/// Reads a description written in HTML, keeping only what can safely be
+/// shown: text, links and pictures on the web, and the few elements giving
+/// them a structure or emphasis. Scripts, styles and embedded content are
+/// dropped, other elements are left out but their text is kept.
+pub fn parse_html(html: &str) -> Vec<Block> {
+    let mut builder = Builder::default();
+    tokenize(html).into_iter().for_each(|token| match token {
+        Token::Text(text) => builder.push_text(&text),
+        Token::Start { name, attributes } => builder.start(&name, &attributes),
+        Token::End(name) => builder.end(&name),
+    });
+    builder.finish()
+}
+
+/// A description as plain text, for what cannot show formatting such as
+/// calendars. Blocks are separated by blank lines, items of lists by line
+/// breaks, and links are followed by where they lead.
+pub fn to_plain_text(html: &str) -> String {
+    let blocks = parse_html(html);
+    let mut text = String::new();
+    blocks.iter().enumerate().for_each(|(index, block)| {
+        if index > 0 {
+            let in_list = |block: &Block| matches!(block.kind, BlockKind::ListItem { .. });
+            text.push_str(if in_list(block) && in_list(&blocks[index - 1]) {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        if let BlockKind::ListItem { depth, marker } = &block.kind {
+            text.push_str(&"  ".repeat(depth - 1));
+            text.push_str(marker);
+            text.push(' ');
+        }
+        text.push_str(&inline_text(&block.content, true));
+    });
+    text
+}
+
+/// The beginning of a description as a single line of plain text, cut
+/// between two words when it is too long.
+pub fn excerpt(html: &str) -> String {
+    let text = parse_html(html)
+        .iter()
+        .map(|block| inline_text(&block.content, false))
+        .collect::<Vec<String>>()
+        .join(" ");
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if text.chars().count() <= EXCERPT_LENGTH {
+        return text;
+    }
+    let cut = text
+        .char_indices()
+        .nth(EXCERPT_LENGTH)
+        .map(|(index, _)| index)
+        .unwrap_or(text.len());
+    let cut = text[..cut].rfind(' ').unwrap_or(cut);
+    format!("{}…", text[..cut].trim_end_matches([',', ';', ':', '.']))
+}
+
+/// Text of a block, pictures being replaced by their description.
+fn inline_text(content: &[Inline], with_links: bool) -> String {
+    let mut text = String::new();
+    let mut link: Option<(&Url, String)> = None;
+    // Links are written once they end, several pieces of text possibly
+    // making them
+    let end_link = |text: &mut String, link: Option<(&Url, String)>| {
+        if let Some((url, link_text)) = link
+            && with_links
+            && link_text.trim() != url.as_str()
+        {
+            text.push_str(&format!(" ({})", url));
+        }
+    };
+    content.iter().for_each(|inline| {
+        let (piece, url) = match inline {
+            Inline::Text { text, style } => (text.as_str(), style.link.as_ref()),
+            Inline::Image { alt, .. } => (alt.as_str(), None),
+        };
+        if link.as_ref().map(|(url, _)| *url) != url {
+            end_link(&mut text, link.take());
+            link = url.map(|url| (url, String::new()));
+        }
+        if let Some((_, link_text)) = link.as_mut() {
+            link_text.push_str(piece);
+        }
+        text.push_str(piece);
+    });
+    end_link(&mut text, link);
+    text
+}
+
+/// Builds the blocks of a description as elements open and close.
+#[derive(Default)]
+struct Builder {
+    blocks: Vec<Block>,
+    kind: Option<BlockKind>,
+    content: Vec<Inline>,
+    /// Inline elements currently open, with the style they give
+    inlines: Vec<(String, Style)>,
+    /// Lists currently open, with the number of their next item if ordered
+    lists: Vec<Option<u32>>,
+}
+
+impl Builder {
+    fn style(&self) -> Style {
+        self.inlines
+            .last()
+            .map(|(_, style)| style.clone())
+            .unwrap_or_default()
+    }
+
+    fn start(&mut self, name: &str, attributes: &[(String, String)]) {
+        let attribute = |wanted: &str| {
+            attributes
+                .iter()
+                .find(|(name, _)| name == wanted)
+                .map(|(_, value)| value.as_str())
+        };
+        match name {
+            "p" | "div" | "blockquote" | "pre" | "section" | "article" | "header" | "footer"
+            | "figure" | "figcaption" | "table" | "tr" => self.start_block(BlockKind::Paragraph),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse().unwrap_or(1);
+                self.start_block(BlockKind::Heading(level))
+            }
+            "ul" => {
+                self.end_block();
+                self.lists.push(None);
+            }
+            "ol" => {
+                self.end_block();
+                let start = attribute("start").and_then(|start| start.parse().ok());
+                self.lists.push(Some(start.unwrap_or(1)));
+            }
+            "li" => {
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => "•".to_string(),
+                };
+                self.start_block(BlockKind::ListItem {
+                    depth: self.lists.len().max(1),
+                    marker,
+                })
+            }
+            "br" => self.push_line_break(),
+            "hr" => self.end_block(),
+            "img" => {
+                let alt = attribute("alt").unwrap_or_default().trim().to_string();
+                match attribute("src").and_then(web_url) {
+                    Some(url) => self.content.push(Inline::Image { url, alt }),
+                    None => self.push_text(&alt),
+                }
+            }
+            "b" | "strong" => self.start_inline(name, |style| style.bold = true),
+            "i" | "em" | "cite" => self.start_inline(name, |style| style.italic = true),
+            // Links to scripts or to files of the user are shown as text
+            "a" => {
+                let url = attribute("href").and_then(web_url);
+                self.start_inline(name, |style| style.link = url)
+            }
+            _ => (),
+        }
+    }
+
+    fn end(&mut self, name: &str) {
+        match name {
+            "p" | "div" | "blockquote" | "pre" | "section" | "article" | "header" | "footer"
+            | "figure" | "figcaption" | "table" | "tr" | "h1" | "h2" | "h3" | "h4" | "h5"
+            | "h6" | "li" => self.end_block(),
+            "ul" | "ol" => {
+                self.end_block();
+                self.lists.pop();
+            }
+            "b" | "strong" | "i" | "em" | "cite" | "a" => {
+                // Elements closed out of order close those opened after them
+                if let Some(index) = self.inlines.iter().rposition(|(open, _)| open == name) {
+                    self.inlines.truncate(index);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn start_inline(&mut self, name: &str, change: impl FnOnce(&mut Style)) {
+        let mut style = self.style();
+        change(&mut style);
+        self.inlines.push((name.to_string(), style));
+    }
+
+    fn start_block(&mut self, kind: BlockKind) {
+        self.end_block();
+        self.kind = Some(kind);
+    }
+
+    /// Whitespace collapses as in browsers, and never begins a line.
+    fn push_text(&mut self, text: &str) {
+        let mut collapsed = String::with_capacity(text.len());
+        let mut after_space = match self.content.last() {
+            Some(Inline::Text { text, .. }) => text.ends_with([' ', '\n']),
+            Some(Inline::Image { .. }) => false,
+            None => true,
+        };
+        text.chars().for_each(|character| {
+            if character.is_ascii_whitespace() {
+                if !after_space {
+                    collapsed.push(' ');
+                }
+                after_space = true;
+            } else {
+                collapsed.push(character);
+                after_space = false;
+            }
+        });
+        if !collapsed.is_empty() {
+            self.push_inline_text(collapsed);
+        }
+    }
+
+    fn push_line_break(&mut self) {
+        if let Some(Inline::Text { text, .. }) = self.content.last_mut() {
+            text.truncate(text.trim_end_matches(' ').len());
+        }
+        self.push_inline_text("\n".to_string());
+    }
+
+    fn push_inline_text(&mut self, text: String) {
+        let style = self.style();
+        match self.content.last_mut() {
+            Some(Inline::Text {
+                text: previous,
+                style: previous_style,
+            }) if *previous_style == style => previous.push_str(&text),
+            _ => self.content.push(Inline::Text { text, style }),
+        }
+    }
+
+    /// Blocks without anything to show are left out.
+    fn end_block(&mut self) {
+        let kind = self.kind.take().unwrap_or(BlockKind::Paragraph);
+        let mut content = std::mem::take(&mut self.content);
+        if let Some(Inline::Text { text, .. }) = content.last_mut() {
+            text.truncate(text.trim_end().len());
+        }
+        content.retain(|inline| match inline {
+            Inline::Text { text, .. } => !text.is_empty(),
+            Inline::Image { .. } => true,
+        });
+        if !content.is_empty() {
+            self.blocks.push(Block { kind, content });
+        }
+    }
+
+    fn finish(mut self) -> Vec<Block> {
+        self.end_block();
+        self.blocks
+    }
+}
+
+/// Only pages on the web are linked to or loaded, other schemes could run
+/// scripts or reach the files of the user.
+fn web_url(value: &str) -> Option<Url> {
+    Url::parse(value.trim())
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https" | "mailto"))
+}
+
+/// Splits HTML into text and tags, skipping comments, declarations and the
+/// content of dropped elements. Broken markup is read as text rather than
+/// refused.
+fn tokenize(html: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            tokens.push(Token::Text(decode_entities(rest)));
+            break;
+        };
+        if start > 0 {
+            tokens.push(Token::Text(decode_entities(&rest[..start])));
+        }
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+        } else if let Some((token, after)) = read_tag(rest) {
+            rest = after;
+            if let Token::Start { name, .. } = &token
+                && DROPPED_ELEMENTS.contains(&name.as_str())
+            {
+                rest = skip_element(rest, name);
+            } else {
+                tokens.push(token);
+            }
+        } else {
+            tokens.push(Token::Text("<".to_string()));
+            rest = &rest[1..];
+        }
+    }
+    tokens
+}
+
+/// Reads the tag `html` starts with, returning what follows it.
+fn read_tag(html: &str) -> Option<(Token, &str)> {
+    let (closing, after) = match html.strip_prefix("</") {
+        Some(after) => (true, after),
+        None => (false, &html[1..]),
+    };
+    let name_length = after
+        .find(|character: char| !character.is_ascii_alphanumeric())
+        .unwrap_or(after.len());
+    if name_length == 0 || !after.starts_with(|character: char| character.is_ascii_alphabetic()) {
+        return None;
+    }
+    let name = after[..name_length].to_ascii_lowercase();
+    let mut rest = &after[name_length..];
+    let mut attributes = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix('>') {
+            rest = after;
+            break;
+        }
+        if let Some(after) = rest.strip_prefix("/>") {
+            rest = after;
+            break;
+        }
+        if rest.is_empty() {
+            break;
+        }
+        let name_length = rest
+            .find(|character: char| {
+                character.is_ascii_whitespace() || matches!(character, '=' | '>' | '/')
+            })
+            .unwrap_or(rest.len())
+            .max(1);
+        let attribute = rest[..name_length].to_ascii_lowercase();
+        rest = rest[name_length..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, after) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let end = after[1..].find(quote).map_or(after.len(), |end| end + 1);
+                        (&after[1..end], after.get(end + 1..).unwrap_or(""))
+                    }
+                    _ => {
+                        let end = after
+                            .find(|character: char| {
+                                character.is_ascii_whitespace() || character == '>'
+                            })
+                            .unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    }
+                };
+                rest = after;
+                decode_entities(value)
+            }
+            None => String::new(),
+        };
+        attributes.push((attribute, value));
+    }
+    let token = if closing {
+        Token::End(name)
+    } else {
+        Token::Start { name, attributes }
+    };
+    Some((token, rest))
+}
+
+/// What follows the end of the element `name`, its content being skipped.
+fn skip_element<'a>(html: &'a str, name: &str) -> &'a str {
+    let closing = format!("</{}", name);
+    let lowercase = html.to_ascii_lowercase();
+    match lowercase.find(&closing) {
+        Some(start) => {
+            let after = &html[start..];
+            after.find('>').map_or("", |end| &after[end + 1..])
+        }
+        None => "",
+    }
+}
+
+/// Replaces character references by the characters they stand for, those
+/// that are unknown being kept as written.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        // Names of references are short, no need to look further
+        let character = rest[1..]
+            .bytes()
+            .take(12)
+            .position(|byte| byte == b';')
+            .and_then(|end| Some((entity(&rest[1..end + 1])?, end + 2)));
+        match character {
+            Some((character, length)) => {
+                decoded.push(character);
+                rest = &rest[length..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hexadecimal) => u32::from_str_radix(hexadecimal, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        // Control characters have nothing to show
+        return char::from_u32(code).filter(|character| !character.is_control());
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "hellip" => '…',
+        "ndash" => '–',
+        "mdash" => '—',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "euro" => '€',
+        "copy" => '©',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Inline {
+        Inline::Text {
+            text: text.to_string(),
+            style: Style::default(),
+        }
+    }
+
+    fn styled(text: &str, style: Style) -> Inline {
+        Inline::Text {
+            text: text.to_string(),
+            style,
+        }
+    }
+
+    fn paragraph(content: Vec<Inline>) -> Block {
+        Block {
+            kind: BlockKind::Paragraph,
+            content,
+        }
+    }
+
+    #[test]
+    fn paragraphs_and_headings_are_blocks() {
+        assert_eq!(
+            parse_html("<h2>Program</h2><p>Talks,\n  then   drinks</p><p></p>"),
+            vec![
+                Block {
+                    kind: BlockKind::Heading(2),
+                    content: vec![text("Program")],
+                },
+                paragraph(vec![text("Talks, then drinks")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn emphasis_is_kept() {
+        let bold = Style {
+            bold: true,
+            ..Default::default()
+        };
+        let both = Style {
+            bold: true,
+            italic: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_html("<p>Bring <strong>food <em>and</em></strong> drinks</p>"),
+            vec![paragraph(vec![
+                text("Bring "),
+                styled("food ", bold),
+                styled("and", both),
+                text(" drinks"),
+            ])]
+        );
+    }
+
+    #[test]
+    fn lists_are_numbered_and_nested() {
+        let kinds = parse_html(
+            "<ol><li>First</li><li>Second<ul><li>Detail</li></ul></li></ol><ul><li>Bullet</li></ul>",
+        )
+        .into_iter()
+        .map(|block| block.kind)
+        .collect::<Vec<BlockKind>>();
+        let item = |depth, marker: &str| BlockKind::ListItem {
+            depth,
+            marker: marker.to_string(),
+        };
+        assert_eq!(
+            kinds,
+            vec![item(1, "1."), item(1, "2."), item(2, "•"), item(1, "•"),]
+        );
+    }
+
+    #[test]
+    fn only_links_to_the_web_are_kept() {
+        let link = |url: &str| Style {
+            link: Some(Url::parse(url).unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_html(
+                "<a href=\"https://mobilizon.example/\">Site</a> \
+                 <a href=\"javascript:alert(1)\">Script</a> \
+                 <a href='file:///etc/passwd'>File</a>"
+            ),
+            vec![paragraph(vec![
+                styled("Site", link("https://mobilizon.example/")),
+                text(" Script File"),
+            ])]
+        );
+    }
+
+    #[test]
+    fn pictures_are_kept_inline() {
+        assert_eq!(
+            parse_html(
+                "<p>Map: <img src=\"https://mobilizon.example/media/map.png\" alt=\"Map\"> \
+                 <img src=\"data:image/png;base64,AAAA\" alt=\"Inline\"></p>"
+            ),
+            vec![paragraph(vec![
+                text("Map: "),
+                Inline::Image {
+                    url: Url::parse("https://mobilizon.example/media/map.png").unwrap(),
+                    alt: "Map".to_string(),
+                },
+                text(" Inline"),
+            ])]
+        );
+    }
+
+    #[test]
+    fn scripts_and_unknown_elements_are_stripped() {
+        assert_eq!(
+            parse_html(
+                "<p onclick=\"steal()\">Hello<script>alert('<p>')</script>\
+                 <style>p { color: red }</style><!-- note --><span class=\"x\"> world</span>\
+                 <IFRAME src=\"https://evil.example\">frame</IFRAME></p>"
+            ),
+            vec![paragraph(vec![text("Hello world")])]
+        );
+    }
+
+    #[test]
+    fn entities_are_decoded() {
+        assert_eq!(
+            parse_html("<p>Fish &amp; chips &lt;3 &#233;t&#xE9; &unknown; R&D</p>"),
+            vec![paragraph(vec![text("Fish & chips <3 été &unknown; R&D")])]
+        );
+    }
+
+    #[test]
+    fn broken_markup_is_read_as_text() {
+        assert_eq!(
+            parse_html("1 < 2 <b>bold"),
+            vec![paragraph(vec![
+                text("1 < 2 "),
+                styled(
+                    "bold",
+                    Style {
+                        bold: true,
+                        ..Default::default()
+                    }
+                ),
+            ])]
+        );
+        assert_eq!(
+            parse_html("<p>Open <a href=\"https://a.example"),
+            vec![paragraph(vec![text("Open")])]
+        );
+    }
+
+    #[test]
+    fn line_breaks_are_kept() {
+        assert_eq!(
+            parse_html("<p>First line <br/> second line</p>"),
+            vec![paragraph(vec![text("First line\nsecond line")])]
+        );
+    }
+
+    #[test]
+    fn plain_text_keeps_the_structure() {
+        assert_eq!(
+            to_plain_text(
+                "<h1>Repair café</h1><p>Bring your <a href=\"https://example.org/list\">broken \
+                 things</a>:</p><ul><li>toasters</li><li>lamps<ol><li>with bulbs</li></ol></li></ul>\
+                 <p>See <a href=\"https://example.org/\">https://example.org/</a></p>"
+            ),
+            "Repair café\n\n\
+             Bring your broken things (https://example.org/list):\n\n\
+             • toasters\n\
+             • lamps\n  \
+             1. with bulbs\n\n\
+             See https://example.org/"
+        );
+    }
+
+    #[test]
+    fn excerpts_are_one_line() {
+        assert_eq!(
+            excerpt(
+                "<h1>Picnic</h1><p>Bring <b>food</b>\nand drinks.</p><ul><li>Rain or shine</li></ul>"
+            ),
+            "Picnic Bring food and drinks. Rain or shine"
+        );
+    }
+
+    #[test]
+    fn long_excerpts_are_cut_between_words() {
+        let excerpt = excerpt(&format!("<p>{}</p>", "word, ".repeat(100)));
+        assert!(excerpt.chars().count() <= EXCERPT_LENGTH + 1);
+        assert!(excerpt.ends_with(" word…"));
+    }
+}
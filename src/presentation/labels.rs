@@ -0,0 +1,72 @@
+use gettextrs::{gettext, ngettext};
+
+use crate::core::event::{EventStatus, TimelineSection};
+use crate::core::participation::ParticipantRole;
+
+/// Translated name of an event status, as shown in the interface.
+pub fn event_status_label(status: &EventStatus) -> String {
+    match status {
+        EventStatus::Confirmed => gettext("Confirmed"),
+        EventStatus::Tentative => gettext("Tentative"),
+        EventStatus::Cancelled => gettext("Cancelled"),
+    }
+}
+
+/// Header of a group of the timeline.
+pub fn timeline_section_label(section: &TimelineSection) -> String {
+    match section {
+        TimelineSection::HappeningNow => gettext("Happening now"),
+        TimelineSection::Today => gettext("Today"),
+        TimelineSection::ThisWeek => gettext("This week"),
+        TimelineSection::ThisMonth => gettext("This month"),
+        TimelineSection::Later => gettext("Later"),
+    }
+}
+
+/// Where the user stands regarding an event, as a sentence.
+pub fn participant_role_label(role: &ParticipantRole) -> String {
+    match role {
+        ParticipantRole::NotApproved => gettext("Waiting for the organizer to approve you"),
+        ParticipantRole::NotConfirmed => gettext("Check your emails to confirm your participation"),
+        ParticipantRole::Rejected => gettext("The organizer declined your participation"),
+        ParticipantRole::Participant => gettext("You are going"),
+        ParticipantRole::Moderator | ParticipantRole::Administrator | ParticipantRole::Creator => {
+            gettext("You are organizing this event")
+        }
+    }
+}
+
+/// Short name of the role of the user, as shown on event cards.
+pub fn participant_role_badge(role: &ParticipantRole) -> String {
+    match role {
+        ParticipantRole::NotApproved => gettext("Pending"),
+        ParticipantRole::NotConfirmed => gettext("Unconfirmed"),
+        ParticipantRole::Rejected => gettext("Declined"),
+        ParticipantRole::Participant => gettext("Participant"),
+        ParticipantRole::Moderator | ParticipantRole::Administrator | ParticipantRole::Creator => {
+            gettext("Organizer")
+        }
+    }
+}
+
+/// How long ago something shown from the cache was fetched.
+pub fn last_updated_label(
+    updated_at: chrono::DateTime<chrono::Utc>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> String {
+    let elapsed = now - updated_at;
+    if elapsed.num_minutes() < 1 {
+        gettext("Updated just now")
+    } else if elapsed.num_hours() < 1 {
+        let minutes = elapsed.num_minutes() as u32;
+        ngettext("Updated {} minute ago", "Updated {} minutes ago", minutes)
+            .replace("{}", &minutes.to_string())
+    } else if elapsed.num_days() < 1 {
+        let hours = elapsed.num_hours() as u32;
+        ngettext("Updated {} hour ago", "Updated {} hours ago", hours)
+            .replace("{}", &hours.to_string())
+    } else {
+        let days = elapsed.num_days() as u32;
+        ngettext("Updated {} day ago", "Updated {} days ago", days).replace("{}", &days.to_string())
+    }
+}
@@ -1 +1,4 @@
+pub mod dates;
 pub mod display;
+pub mod html;
+pub mod labels;
@@ -1,9 +1,16 @@
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use gtk::glib;
+use gtk::glib::subclass::Signal;
+use std::str::FromStr;
+use std::sync::OnceLock;
 
 use crate::core::category::Category;
+use crate::core::event::EventStatus;
+use crate::core::language::Language;
+use crate::presentation::labels::event_status_label;
 use crate::sidebar_row;
+use crate::sidebar_row::MobilisationSidebarRow;
 
 mod imp {
     use super::*;
@@ -11,6 +18,12 @@ mod imp {
     #[derive(Default, gtk::CompositeTemplate)]
     #[template(resource = "/space/soulie/mobilisation/sidebar.ui")]
     pub struct MobilisationSidebar {
+        #[template_child]
+        pub view_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub timeline_row: TemplateChild<gtk::ListBoxRow>,
+        #[template_child]
+        pub my_events_row: TemplateChild<gtk::ListBoxRow>,
         #[template_child]
         pub content: TemplateChild<gtk::Box>,
         #[template_child]
@@ -19,6 +32,10 @@ mod imp {
         pub spinner: TemplateChild<adw::Spinner>,
         #[template_child]
         pub category_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub language_list: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub status_list: TemplateChild<gtk::ListBox>,
     }
 
     #[glib::object_subclass]
@@ -36,7 +53,55 @@ mod imp {
         }
     }
 
-    impl ObjectImpl for MobilisationSidebar {}
+    impl ObjectImpl for MobilisationSidebar {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("filters-changed").build(),
+                    Signal::builder("view-changed")
+                        .param_types([String::static_type()])
+                        .build(),
+                ]
+            })
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.view_list.select_row(Some(&*self.timeline_row));
+            self.view_list.connect_row_selected(glib::clone!(
+                #[weak(rename_to = sidebar)]
+                self.obj(),
+                move |_, row| {
+                    if let Some(row) = row {
+                        sidebar
+                            .emit_by_name::<()>("view-changed", &[&row.widget_name().to_string()]);
+                    }
+                }
+            ));
+            // Rows are toggled rather than selected so that several filters
+            // of the same section can be active at once.
+            [&self.category_list, &self.language_list, &self.status_list]
+                .iter()
+                .for_each(|list| {
+                    list.connect_row_activated(|_, row| {
+                        if let Some(row) = row.downcast_ref::<MobilisationSidebarRow>() {
+                            row.set_active(!row.active());
+                        }
+                    });
+                });
+            // Statuses come from the API schema rather than from the
+            // instance, so they do not need to wait for the configuration.
+            let obj = self.obj();
+            EventStatus::ALL.iter().for_each(|status| {
+                let row = glib::Object::builder::<sidebar_row::MobilisationSidebarRow>()
+                    .property("label", event_status_label(status))
+                    .property("value", status.as_str())
+                    .build();
+                obj.append_filter_row(&self.status_list, &row);
+            });
+        }
+    }
     impl WidgetImpl for MobilisationSidebar {}
     impl BoxImpl for MobilisationSidebar {}
 }
@@ -48,22 +113,137 @@ glib::wrapper! {
 }
 
 impl MobilisationSidebar {
-    pub fn show_error(&self) {
+    /// Called whenever a filter row is turned on or off.
+    pub fn connect_filters_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "filters-changed",
+            false,
+            glib::closure_local!(move |sidebar: &Self| f(sidebar)),
+        )
+    }
+
+    /// Called with the name of the view picked at the top of the sidebar,
+    /// either `timeline` or `my-events`.
+    pub fn connect_view_changed<F: Fn(&Self, &str) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "view-changed",
+            false,
+            glib::closure_local!(move |sidebar: &Self, view: String| f(sidebar, &view)),
+        )
+    }
+
+    /// The events of the user can only be listed once they are logged in.
+    pub fn set_my_events_visible(&self, visible: bool) {
+        let imp = self.imp();
+        imp.my_events_row.set_visible(visible);
+        if !visible && imp.view_list.selected_row().as_ref() == Some(&*imp.my_events_row) {
+            imp.view_list.select_row(Some(&*imp.timeline_row));
+        }
+    }
+
+    pub fn show_error(&self, description: &str) {
+        self.imp().error.set_description(Some(description));
         self.imp().spinner.set_visible(false);
         self.imp().content.set_visible(false);
         self.imp().error.set_visible(true);
     }
 
+    pub fn show_loading(&self) {
+        self.imp().spinner.set_visible(true);
+        self.imp().content.set_visible(false);
+        self.imp().error.set_visible(false);
+        self.imp().category_list.remove_all();
+        self.imp().language_list.remove_all();
+    }
+
     pub fn append_categories(&self, categories: &Vec<Category>) -> () {
         self.imp().spinner.set_visible(false);
         self.imp().content.set_visible(true);
         self.imp().error.set_visible(false);
         let _ = categories.into_iter().for_each(|category| {
             let row = glib::Object::builder::<sidebar_row::MobilisationSidebarRow>()
-                    .property("label", category.label.clone())
-                    .build();
-            glib::g_log!(glib::LogLevel::Debug, "Creating a row with label {}", category.label);
-            self.imp().category_list.append(&row);
+                .property("label", category.label.clone())
+                .property("value", category.id.clone())
+                .build();
+            glib::g_log!(
+                glib::LogLevel::Debug,
+                "Creating a row with label {}",
+                category.label
+            );
+            self.append_filter_row(&self.imp().category_list, &row);
+        });
+    }
+
+    pub fn append_languages(&self, languages: &[Language]) {
+        languages.iter().for_each(|language| {
+            let row = glib::Object::builder::<sidebar_row::MobilisationSidebarRow>()
+                .property("label", language.name())
+                .property("value", language.code.clone())
+                .build();
+            self.append_filter_row(&self.imp().language_list, &row);
         });
     }
+
+    /// Every filter row currently turned on, all sections included.
+    pub fn active_rows(&self) -> Vec<MobilisationSidebarRow> {
+        [
+            &self.imp().category_list,
+            &self.imp().language_list,
+            &self.imp().status_list,
+        ]
+        .iter()
+        .flat_map(|list| Self::rows(list))
+        .filter(|row| row.active())
+        .collect()
+    }
+
+    /// Ids of the categories the timeline should be restricted to.
+    pub fn selected_categories(&self) -> Vec<String> {
+        Self::selected_values(&self.imp().category_list)
+    }
+
+    /// Codes of the languages the timeline should be restricted to.
+    pub fn selected_languages(&self) -> Vec<String> {
+        Self::selected_values(&self.imp().language_list)
+    }
+
+    /// Statuses the timeline should be restricted to.
+    pub fn selected_statuses(&self) -> Vec<EventStatus> {
+        Self::selected_values(&self.imp().status_list)
+            .iter()
+            .filter_map(|value| EventStatus::from_str(value).ok())
+            .collect()
+    }
+
+    fn selected_values(list: &gtk::ListBox) -> Vec<String> {
+        Self::rows(list)
+            .into_iter()
+            .filter(|row| row.active())
+            .map(|row| row.value())
+            .collect()
+    }
+
+    fn append_filter_row(&self, list: &gtk::ListBox, row: &MobilisationSidebarRow) {
+        row.connect_active_notify(glib::clone!(
+            #[weak(rename_to = sidebar)]
+            self,
+            move |_| sidebar.emit_by_name::<()>("filters-changed", &[])
+        ));
+        list.append(row);
+    }
+
+    fn rows(list: &gtk::ListBox) -> Vec<MobilisationSidebarRow> {
+        let mut rows = Vec::new();
+        let mut child = list.first_child();
+        while let Some(widget) = child {
+            child = widget.next_sibling();
+            if let Ok(row) = widget.downcast::<MobilisationSidebarRow>() {
+                rows.push(row);
+            }
+        }
+        rows
+    }
 }
@@ -1,7 +1,7 @@
-use adw::subclass::prelude::*;
 use adw::prelude::*;
-use gtk::glib;
+use adw::subclass::prelude::*;
 use core::cell;
+use gtk::glib;
 
 mod imp {
     use super::*;
@@ -12,8 +12,15 @@ mod imp {
     pub struct MobilisationSidebarRow {
         #[property(get, set)]
         label: cell::RefCell<String>,
+        /// What the row filters on, e.g. the id of a category
+        #[property(get, set)]
+        value: cell::RefCell<String>,
+        #[property(get, set)]
+        active: cell::Cell<bool>,
+        #[template_child]
+        pub row_label: TemplateChild<gtk::Label>,
         #[template_child]
-        pub row_label: TemplateChild<gtk::Label>
+        pub check: TemplateChild<gtk::Image>,
     }
 
     #[glib::object_subclass]
@@ -35,7 +42,11 @@ mod imp {
         fn constructed(&self) {
             self.parent_constructed();
             let a = self.obj();
-            a.bind_property("label", &self.row_label.get(), "label").build();
+            a.bind_property("label", &self.row_label.get(), "label")
+                .build();
+            a.bind_property("active", &self.check.get(), "visible")
+                .sync_create()
+                .build();
         }
     }
     impl WidgetImpl for MobilisationSidebarRow {}
@@ -17,19 +17,40 @@
  *
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
+use adw::prelude::*;
 use adw::subclass::prelude::*;
-use gtk::prelude::*;
+use gettextrs::gettext;
 use gtk::{gio, glib};
+use std::cell::{Cell, OnceCell, RefCell};
+use std::sync::Arc;
+use url::Url;
+use uuid::Uuid;
 
+use crate::calendar_export::{export_calendar, show_export_error};
+use crate::clock::{self, ClockWatch};
+use crate::event_page::MobilisationEventPage;
 use crate::event_preview::MobilisationEventPreview;
 use crate::event_preview_model::MobilisationEventPreviewModel;
+use crate::filter_chip::MobilisationFilterChip;
+use crate::instance_picker::MobilisationInstancePicker;
+use crate::login_dialog::MobilisationLoginDialog;
 use crate::sidebar::MobilisationSidebar;
 
+use crate::core::category::Category;
 use crate::core::event::Event;
-use crate::http_client;
-use crate::infra::config::fetch_config;
-use crate::infra::events::fetch_events;
-use crate::runtime;
+use crate::core::event_search::EventSearch;
+use crate::core::icalendar::CalendarEvent;
+use crate::core::language::language_codes_from_locales;
+use crate::core::pagination::Pagination;
+use crate::core::participation::{ParticipantRole, split_upcoming};
+use crate::infra::cache::{Cache, Cached, Loaded};
+use crate::infra::client::{MobilizonApi, MobilizonClient, SessionChange};
+use crate::infra::config::ConfigFetchError;
+use crate::infra::events::{EventDecodeError, EventsFetchError, fetch_all_events};
+use crate::infra::graphql::GraphQLErrors;
+use crate::infra::keyring::SessionKeyring;
+use crate::presentation::labels::{last_updated_label, timeline_section_label};
+use crate::{runtime, settings};
 
 mod imp {
     use super::*;
@@ -37,14 +58,86 @@ mod imp {
     #[derive(Debug, Default, gtk::CompositeTemplate)]
     #[template(resource = "/space/soulie/mobilisation/window.ui")]
     pub struct MobilisationWindow {
+        #[template_child]
+        pub main_stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub onboarding_picker: TemplateChild<MobilisationInstancePicker>,
         #[template_child]
         pub sidebar: TemplateChild<MobilisationSidebar>,
         #[template_child]
         pub sidebar_show: TemplateChild<gtk::ToggleButton>,
         #[template_child]
+        pub search_button: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub search_bar: TemplateChild<gtk::SearchBar>,
+        #[template_child]
+        pub search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub filter_chips: TemplateChild<gtk::FlowBox>,
+        #[template_child]
+        pub view_stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub cache_banner: TemplateChild<adw::Banner>,
+        #[template_child]
+        pub timeline_stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub empty_results: TemplateChild<adw::StatusPage>,
+        #[template_child]
+        pub timeline_error: TemplateChild<adw::StatusPage>,
+        #[template_child]
+        pub retry_button: TemplateChild<gtk::Button>,
+        #[template_child]
         pub split_view: TemplateChild<adw::OverlaySplitView>,
         #[template_child]
+        pub navigation_view: TemplateChild<adw::NavigationView>,
+        #[template_child]
         pub event_previews: TemplateChild<gtk::ListView>,
+        #[template_child]
+        pub timeline_scroll: TemplateChild<gtk::ScrolledWindow>,
+        #[template_child]
+        pub page_spinner: TemplateChild<adw::Spinner>,
+        #[template_child]
+        pub end_of_results: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub my_events_stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub my_events_error: TemplateChild<adw::StatusPage>,
+        #[template_child]
+        pub my_events_retry_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub upcoming_heading: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub upcoming_events: TemplateChild<gtk::ListView>,
+        #[template_child]
+        pub past_heading: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub past_events: TemplateChild<gtk::ListView>,
+        pub settings: OnceCell<gio::Settings>,
+        pub event_store: OnceCell<gio::ListStore>,
+        /// Groups the timeline by when events happen
+        pub section_sorter: OnceCell<gtk::CustomSorter>,
+        pub section_watch: RefCell<Option<ClockWatch>>,
+        pub upcoming_store: OnceCell<gio::ListStore>,
+        pub past_store: OnceCell<gio::ListStore>,
+        /// The instance the timeline comes from
+        pub api: RefCell<Option<Arc<dyn MobilizonApi>>>,
+        /// What was last fetched from the instance
+        pub cache: RefCell<Option<Cache>>,
+        /// When the events shown were fetched, as long as they come from the
+        /// cache rather than from the instance
+        pub cached_since: Cell<Option<chrono::DateTime<chrono::Utc>>>,
+        /// Categories of the instance, kept to label them on event pages
+        pub categories: RefCell<Vec<Category>>,
+        pub search: RefCell<EventSearch>,
+        pub pagination: Cell<Pagination>,
+        pub loading_page: Cell<bool>,
+        pub page_request: RefCell<Option<tokio::task::AbortHandle>>,
+        /// Bumped every time the timeline is emptied so that pages requested
+        /// for a previous timeline are dropped when they arrive.
+        pub timeline_generation: Cell<u64>,
+        /// Bumped every time the instance is loaded again so that the config
+        /// of a previous instance is dropped when it arrives.
+        pub instance_generation: Cell<u64>,
     }
 
     #[glib::object_subclass]
@@ -55,6 +148,15 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+            klass.install_action("win.search", None, |window, _, _| {
+                window.imp().search_bar.set_search_mode(true);
+                window.imp().search_entry.grab_focus();
+            });
+            klass.install_action("win.login", None, |window, _, _| show_login(window));
+            klass.install_action("win.logout", None, |window, _, _| logout(window));
+            klass.install_action("win.export-events", None, |window, _, _| {
+                export_timeline(window)
+            });
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -62,48 +164,108 @@ mod imp {
         }
     }
 
-    fn load_config(sidebar: &MobilisationSidebar) {
+    fn load_config(obj: &super::MobilisationWindow, api: Arc<dyn MobilizonApi>, cache: Cache) {
+        let generation = obj.imp().instance_generation.get();
         let (sender, receiver) = async_channel::unbounded();
         runtime().spawn(async move {
-            let _ = sender
-                .send(
-                    fetch_config(
-                        &url::Url::parse("https://mobilizon.fr/api").unwrap(),
-                        &http_client(),
-                    )
-                    .await,
-                )
-                .await;
+            match cache.load_config().await {
+                Ok(Some(cached)) => {
+                    let _ = sender.send(Loaded::Cached(cached)).await;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    glib::g_log!(
+                        glib::LogLevel::Warning,
+                        "Error reading cached config : {}",
+                        err
+                    );
+                }
+            }
+            let fetched = api.fetch_config().await;
+            if let Ok(config) = &fetched
+                && let Err(err) = cache.save_config(config).await
+            {
+                glib::g_log!(glib::LogLevel::Warning, "Error caching config : {}", err);
+            }
+            let _ = sender.send(Loaded::Fetched(fetched)).await;
         });
         glib::spawn_future_local(glib::clone!(
-            #[strong]
-            sidebar,
+            #[weak]
+            obj,
             async move {
-                receiver
-                    .recv()
-                    .await
-                    .map(|value| match value {
-                        Ok(v) => sidebar.append_categories(&v.categories),
-                        Err(err) => {
-                            glib::g_log!(glib::LogLevel::Warning, "Error fetching config : {}", err);
-                            sidebar.show_error();
+                let sidebar = obj.imp().sidebar.get();
+                let mut shown = None;
+                while let Ok(loaded) = receiver.recv().await {
+                    // The instance changed in the meantime
+                    if obj.imp().instance_generation.get() != generation {
+                        return;
+                    }
+                    // A config sent in full despite errors is used all the same
+                    let loaded = match loaded {
+                        Loaded::Fetched(Err(ConfigFetchError::GraphQLErrors(GraphQLErrors {
+                            errors,
+                            partial_data: Some(config),
+                        }))) => {
+                            glib::g_log!(
+                                glib::LogLevel::Warning,
+                                "Error fetching config : {}",
+                                GraphQLErrors {
+                                    errors,
+                                    partial_data: Some(()),
+                                }
+                            );
+                            Loaded::Fetched(Ok(config))
                         }
-                    })
-                    .map_err(|err| {
-                        glib::g_log!(glib::LogLevel::Error, "Channel error : {}", err);
-                        sidebar.show_error();
-                    })
+                        loaded => loaded,
+                    };
+                    match loaded {
+                        Loaded::Cached(cached) => {
+                            sidebar.append_categories(&cached.value.categories);
+                            sidebar.append_languages(&cached.value.languages);
+                            obj.imp()
+                                .categories
+                                .replace(cached.value.categories.clone());
+                            shown = Some(cached.value);
+                        }
+                        Loaded::Fetched(Ok(v)) => {
+                            if let Some(shown) = &shown {
+                                if shown.categories == v.categories
+                                    && shown.languages == v.languages
+                                {
+                                    continue;
+                                }
+                                // Filters on rows that are going away no longer apply
+                                sidebar.show_loading();
+                            }
+                            sidebar.append_categories(&v.categories);
+                            sidebar.append_languages(&v.languages);
+                            obj.imp().categories.replace(v.categories);
+                            if shown.is_some() && sync_filters(&obj) {
+                                reset_event_timeline(&obj);
+                            }
+                        }
+                        Loaded::Fetched(Err(err)) => {
+                            glib::g_log!(
+                                glib::LogLevel::Warning,
+                                "Error fetching config : {}",
+                                err
+                            );
+                            if shown.is_none() {
+                                sidebar.show_error(&err.to_string());
+                            }
+                        }
+                    }
+                }
             }
         ));
     }
 
-    fn create_event_timeline(window: &MobilisationWindow, events: &Vec<Event>) {
-        let models = events
-            .into_iter()
-            .map(|event| MobilisationEventPreviewModel::new(event))
-            .collect::<Vec<MobilisationEventPreviewModel>>();
-        let store = gio::ListStore::new::<MobilisationEventPreviewModel>();
-        store.extend_from_slice(&models);
+    /// Distance from the bottom of the timeline, in pixels, under which the
+    /// next page starts loading.
+    const NEAR_END_THRESHOLD: f64 = 600.0;
+
+    /// Builds the event cards of the timeline and of the events of the user.
+    fn make_event_factory(obj: &super::MobilisationWindow) -> gtk::SignalListItemFactory {
         let factory = gtk::SignalListItemFactory::new();
         factory.connect_setup(move |_, list_item| {
             let preview = MobilisationEventPreview::new();
@@ -112,93 +274,951 @@ mod imp {
                 .expect("Could not downcast Object to ListItem.")
                 .set_child(Some(&preview));
         });
-        factory.connect_bind(move |_, list_item| {
-            let model = list_item
-                .downcast_ref::<gtk::ListItem>()
-                .expect("Could not downcast Object to ListItem.")
-                .item()
-                .and_downcast::<MobilisationEventPreviewModel>()
-                .expect("Could not downcast EventPreviewModel from ListItem item.");
-            let event_preview = list_item
+        factory.connect_bind(glib::clone!(
+            #[weak]
+            obj,
+            move |_, list_item| {
+                let model = list_item
+                    .downcast_ref::<gtk::ListItem>()
+                    .expect("Could not downcast Object to ListItem.")
+                    .item()
+                    .and_downcast::<MobilisationEventPreviewModel>()
+                    .expect("Could not downcast EventPreviewModel from ListItem item.");
+                let event_preview = list_item
+                    .downcast_ref::<gtk::ListItem>()
+                    .expect("Could not downcast Object to ListItem.")
+                    .child()
+                    .and_downcast::<MobilisationEventPreview>()
+                    .expect("Could not downcast EventPreviewModel from ListItem item.");
+                // TODO : This should be improved
+                event_preview
+                    .imp()
+                    .event_name
+                    .get()
+                    .set_label(model.title().as_str());
+                event_preview
+                    .imp()
+                    .event_description
+                    .get()
+                    .set_label(model.description().as_str());
+                event_preview.set_excerpt(&model.excerpt());
+                // Only the items shown are bound, so only their time is kept up to date
+                event_preview.watch_time(model.event());
+                event_preview.set_status(model.event_status());
+                event_preview.set_role(model.participant_role());
+                event_preview.set_api(obj.imp().api.borrow().clone());
+                match model.picture_url() {
+                    Some(picture_url) => event_preview.set_picture_url(picture_url),
+                    None => event_preview.show_placeholder(&model.title()),
+                }
+            }
+        ));
+        // Recycled items must not show the picture of their previous event,
+        // nor receive it late, nor follow its time.
+        factory.connect_unbind(move |_, list_item| {
+            if let Some(event_preview) = list_item
                 .downcast_ref::<gtk::ListItem>()
                 .expect("Could not downcast Object to ListItem.")
                 .child()
                 .and_downcast::<MobilisationEventPreview>()
-                .expect("Could not downcast EventPreviewModel from ListItem item.");
-            // TODO : This should be improved
-            event_preview
-                .imp()
-                .event_name
-                .get()
-                .set_label(model.title().as_str());
-            event_preview
-                .imp()
-                .event_description
-                .get()
-                .set_label(model.description().as_str());
-            event_preview
-                .imp()
-                .time
-                .get()
-                .set_label(model.human_readable_time().as_str());
-            println!("picture replaced for url {:?}", model.picture_url());
-            model.picture_url().map(|v| event_preview.set_picture_url(v));
+            {
+                event_preview.clear_picture();
+                event_preview.unwatch_time();
+            }
+        });
+        factory
+    }
+
+    /// Builds the headers of the groups of the timeline.
+    fn make_section_header_factory() -> gtk::SignalListItemFactory {
+        let factory = gtk::SignalListItemFactory::new();
+        factory.connect_setup(move |_, list_header| {
+            let label = gtk::Label::builder()
+                .xalign(0.0)
+                .margin_top(12)
+                .margin_bottom(6)
+                .css_classes(["heading"])
+                .build();
+            list_header
+                .downcast_ref::<gtk::ListHeader>()
+                .expect("Could not downcast Object to ListHeader.")
+                .set_child(Some(&label));
         });
-        let selection_model = gtk::NoSelection::new(Some(store));
-        window.event_previews.set_model(Some(&selection_model));
-        window.event_previews.set_factory(Some(&factory));
+        factory.connect_bind(move |_, list_header| {
+            let list_header = list_header
+                .downcast_ref::<gtk::ListHeader>()
+                .expect("Could not downcast Object to ListHeader.");
+            // Every event of a group is in the same section, the first one tells which
+            if let (Some(model), Some(label)) = (
+                list_header
+                    .item()
+                    .and_downcast::<MobilisationEventPreviewModel>(),
+                list_header.child().and_downcast::<gtk::Label>(),
+            ) {
+                label.set_label(&timeline_section_label(&model.timeline_section()));
+            }
+        });
+        factory
+    }
+
+    /// Shows the events of `model` in `list_view`, opening them on activation.
+    fn setup_event_list(
+        obj: &super::MobilisationWindow,
+        list_view: &gtk::ListView,
+        model: &impl IsA<gio::ListModel>,
+    ) {
+        let selection_model = gtk::NoSelection::new(Some(model.clone()));
+        list_view.set_model(Some(&selection_model));
+        list_view.set_factory(Some(&make_event_factory(obj)));
+        list_view.connect_activate(glib::clone!(
+            #[weak]
+            obj,
+            move |list_view, position| {
+                if let Some(model) = list_view
+                    .model()
+                    .and_then(|model| model.item(position))
+                    .and_downcast::<MobilisationEventPreviewModel>()
+                {
+                    show_event(&obj, &model);
+                }
+            }
+        ));
+    }
+
+    fn setup_event_timeline(obj: &super::MobilisationWindow) {
+        let window = obj.imp();
+        let store = gio::ListStore::new::<MobilisationEventPreviewModel>();
+        let section_sorter = gtk::CustomSorter::new(|a, b| {
+            let section = |item: &glib::Object| {
+                item.downcast_ref::<MobilisationEventPreviewModel>()
+                    .map(MobilisationEventPreviewModel::timeline_section)
+            };
+            section(a).cmp(&section(b)).into()
+        });
+        // Events come sorted by start time, which the sort keeps within groups
+        let sections = gtk::SortListModel::builder()
+            .model(&store)
+            .section_sorter(&section_sorter)
+            .build();
+        setup_event_list(obj, &window.event_previews, &sections);
+        window
+            .event_previews
+            .set_header_factory(Some(&make_section_header_factory()));
+        let _ = window.event_store.set(store);
+        let _ = window.section_sorter.set(section_sorter);
+
+        // Picks up from the page that failed, keeping the events already shown
+        window.retry_button.connect_clicked(glib::clone!(
+            #[weak]
+            obj,
+            move |_| {
+                update_timeline_state(&obj);
+                load_next_page(&obj);
+            }
+        ));
+        window.cache_banner.connect_button_clicked(glib::clone!(
+            #[weak]
+            obj,
+            move |_| {
+                if let Some(updated_at) = obj.imp().cached_since.get() {
+                    show_cache_banner(&obj, updated_at, false);
+                }
+                load_next_page(&obj);
+            }
+        ));
+
+        let adjustment = window.timeline_scroll.vadjustment();
+        adjustment.connect_value_changed(glib::clone!(
+            #[weak]
+            obj,
+            move |_| load_more_if_needed(&obj)
+        ));
+        // Also fires when a page is appended, which keeps loading until the
+        // timeline is taller than the window.
+        adjustment.connect_changed(glib::clone!(
+            #[weak]
+            obj,
+            move |_| load_more_if_needed(&obj)
+        ));
     }
 
-    fn load_events(obj: &super::MobilisationWindow) {
+    fn setup_my_events(obj: &super::MobilisationWindow) {
+        let window = obj.imp();
+        let upcoming_store = gio::ListStore::new::<MobilisationEventPreviewModel>();
+        setup_event_list(obj, &window.upcoming_events, &upcoming_store);
+        let _ = window.upcoming_store.set(upcoming_store);
+        let past_store = gio::ListStore::new::<MobilisationEventPreviewModel>();
+        setup_event_list(obj, &window.past_events, &past_store);
+        let _ = window.past_store.set(past_store);
+
+        window.my_events_retry_button.connect_clicked(glib::clone!(
+            #[weak]
+            obj,
+            move |_| load_my_events(&obj)
+        ));
+        window.sidebar.connect_view_changed(glib::clone!(
+            #[weak]
+            obj,
+            move |_, view| {
+                let window = obj.imp();
+                window.view_stack.set_visible_child_name(view);
+                let is_timeline = view == "timeline";
+                window.search_button.set_visible(is_timeline);
+                if !is_timeline {
+                    window.search_bar.set_search_mode(false);
+                    // Joining or leaving from an event page changes the list
+                    load_my_events(&obj);
+                }
+            }
+        ));
+    }
+
+    fn load_my_events(obj: &super::MobilisationWindow) {
+        let window = obj.imp();
+        let Some(api) = window.api.borrow().clone() else {
+            return;
+        };
+        window.my_events_stack.set_visible_child_name("loading");
+        let (sender, receiver) = async_channel::unbounded();
+        runtime().spawn(async move {
+            let _ = sender.send(api.fetch_my_events().await).await;
+        });
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            obj,
+            async move {
+                let window = obj.imp();
+                let _ = receiver
+                    .recv()
+                    .await
+                    .map(|value| match value {
+                        Ok(participations) => {
+                            let (upcoming, past) =
+                                split_upcoming(participations, chrono::Utc::now());
+                            window.upcoming_heading.set_visible(!upcoming.is_empty());
+                            window.past_heading.set_visible(!past.is_empty());
+                            let is_empty = upcoming.is_empty() && past.is_empty();
+                            [
+                                (window.upcoming_store.get(), upcoming),
+                                (window.past_store.get(), past),
+                            ]
+                            .into_iter()
+                            .for_each(|(store, participations)| {
+                                let models = participations
+                                    .iter()
+                                    .map(MobilisationEventPreviewModel::with_role)
+                                    .collect::<Vec<MobilisationEventPreviewModel>>();
+                                if let Some(store) = store {
+                                    store.remove_all();
+                                    store.extend_from_slice(&models);
+                                }
+                            });
+                            window.my_events_stack.set_visible_child_name(if is_empty {
+                                "empty"
+                            } else {
+                                "events"
+                            });
+                        }
+                        Err(err) => {
+                            glib::g_log!(
+                                glib::LogLevel::Warning,
+                                "Error fetching the events of the user : {}",
+                                err
+                            );
+                            window
+                                .my_events_error
+                                .set_description(Some(&err.to_string()));
+                            window.my_events_stack.set_visible_child_name("error");
+                        }
+                    })
+                    .map_err(|err| {
+                        glib::g_log!(glib::LogLevel::Error, "Channel error : {}", err);
+                    });
+            }
+        ));
+    }
+
+    fn show_event(obj: &super::MobilisationWindow, model: &MobilisationEventPreviewModel) {
+        let imp = obj.imp();
+        let Some(api) = imp.api.borrow().clone() else {
+            return;
+        };
+        let (Some(instance_url), Ok(uuid)) = (instance_url(obj), Uuid::parse_str(&model.uuid()))
+        else {
+            return;
+        };
+        let page = MobilisationEventPage::new(
+            api,
+            imp.cache.borrow().clone(),
+            &instance_url,
+            uuid,
+            &model.title(),
+            imp.categories.borrow().clone(),
+        );
+        page.connect_role_changed(glib::clone!(
+            #[weak]
+            obj,
+            move |_, role| show_role(&obj, uuid, role)
+        ));
+        imp.navigation_view.push(&page);
+    }
+
+    /// Shows the role of the user on every card of the event, the timeline
+    /// and their own events alike.
+    fn show_role(obj: &super::MobilisationWindow, uuid: Uuid, role: Option<ParticipantRole>) {
+        let imp = obj.imp();
+        let uuid = uuid.to_string();
+        [
+            imp.event_store.get(),
+            imp.upcoming_store.get(),
+            imp.past_store.get(),
+        ]
+        .into_iter()
+        .flatten()
+        .for_each(|store| {
+            (0..store.n_items()).for_each(|position| {
+                let Some(model) = store
+                    .item(position)
+                    .and_downcast::<MobilisationEventPreviewModel>()
+                    .filter(|model| model.uuid() == uuid)
+                else {
+                    return;
+                };
+                model.set_participant_role(role);
+                // Cards only read their model when bound
+                store.items_changed(position, 1, 1);
+            })
+        });
+    }
+
+    fn is_near_end(adjustment: &gtk::Adjustment) -> bool {
+        adjustment.value() + adjustment.page_size() >= adjustment.upper() - NEAR_END_THRESHOLD
+    }
+
+    fn load_more_if_needed(obj: &super::MobilisationWindow) {
+        let window = obj.imp();
+        let near_end = is_near_end(&window.timeline_scroll.vadjustment());
+        window
+            .end_of_results
+            .set_visible(near_end && window.pagination.get().is_exhausted());
+        // Cached events are only refreshed on demand, not paged through
+        if near_end && window.cached_since.get().is_none() {
+            load_next_page(obj);
+        }
+    }
+
+    /// Moves the events of the timeline to the group they fall in now, then
+    /// waits for the next one to change group.
+    fn refresh_timeline_sections(obj: &super::MobilisationWindow) {
+        let window = obj.imp();
+        let watch = window.event_store.get().and_then(|store| {
+            update_timeline_sections(obj, store, chrono::Local::now()).map(|due| {
+                clock::watch(
+                    due,
+                    glib::clone!(
+                        #[weak]
+                        obj,
+                        #[weak]
+                        store,
+                        #[upgrade_or]
+                        None,
+                        move |now| update_timeline_sections(&obj, &store, now)
+                    ),
+                )
+            })
+        });
+        window.section_watch.replace(watch);
+    }
+
+    /// Tells when the events of the timeline may change group next.
+    fn update_timeline_sections(
+        obj: &super::MobilisationWindow,
+        store: &gio::ListStore,
+        now: chrono::DateTime<chrono::Local>,
+    ) -> Option<chrono::DateTime<chrono::Local>> {
+        let window = obj.imp();
+        let models = store
+            .iter::<MobilisationEventPreviewModel>()
+            .flatten()
+            .collect::<Vec<MobilisationEventPreviewModel>>();
+        // Every event is updated, even after one moved
+        let moved = models
+            .iter()
+            .filter(|model| model.update_timeline_section(now))
+            .count();
+        if moved > 0
+            && let Some(sorter) = window.section_sorter.get()
+        {
+            sorter.changed(gtk::SorterChange::Different);
+        }
+        models
+            .iter()
+            .filter_map(|model| model.next_section_change(now))
+            .min()
+    }
+
+    /// Shows the empty state once the whole search came back without events.
+    fn update_timeline_state(obj: &super::MobilisationWindow) {
+        let window = obj.imp();
+        let is_empty = window
+            .event_store
+            .get()
+            .is_none_or(|store| store.n_items() == 0);
+        // There is nothing to export until an event shows up
+        obj.action_set_enabled("win.export-events", !is_empty);
+        if is_empty && window.pagination.get().is_exhausted() {
+            let description = if window.search.borrow().term.is_empty() {
+                gettext("There are no upcoming events on this instance.")
+            } else {
+                gettext("Try a different search.")
+            };
+            window.empty_results.set_description(Some(&description));
+            window.timeline_stack.set_visible_child_name("empty");
+        } else {
+            window.timeline_stack.set_visible_child_name("events");
+        }
+    }
+
+    /// Empties the timeline and starts loading it again from the first page,
+    /// dropping any page still being fetched.
+    fn reset_event_timeline(obj: &super::MobilisationWindow) {
+        let window = obj.imp();
+        window
+            .timeline_generation
+            .set(window.timeline_generation.get() + 1);
+        if let Some(request) = window.page_request.take() {
+            request.abort();
+        }
+        window.pagination.set(Pagination::default());
+        // Every page of the new timeline starts from the same moment
+        window.search.borrow_mut().begins_on = Some(chrono::Utc::now());
+        window.loading_page.set(false);
+        window.page_spinner.set_visible(false);
+        window.end_of_results.set_visible(false);
+        window.cached_since.set(None);
+        window.cache_banner.set_revealed(false);
+        if let Some(store) = window.event_store.get() {
+            store.remove_all();
+        }
+        update_timeline_state(obj);
+        load_next_page(obj);
+    }
+
+    fn load_next_page(obj: &super::MobilisationWindow) {
+        let window = obj.imp();
+        let pagination = window.pagination.get();
+        let (Some(page), Some(api)) = (pagination.next_page(), window.api.borrow().clone()) else {
+            return;
+        };
+        if window.loading_page.replace(true) {
+            return;
+        }
+        window.page_spinner.set_visible(true);
+        let generation = window.timeline_generation.get();
+        let search = window.search.borrow().clone();
+        // Only the beginning of the unfiltered timeline is worth keeping
+        let cache = window
+            .cache
+            .borrow()
+            .clone()
+            .filter(|_| page == 1 && search.is_unfiltered());
+        let show_cached = window.cached_since.get().is_none();
+        let (sender, receiver) = async_channel::unbounded();
+        let request = runtime().spawn(async move {
+            if let Some(cache) = cache.as_ref().filter(|_| show_cached) {
+                match cache.load_timeline().await {
+                    Ok(Some(cached)) => {
+                        let _ = sender.send(Loaded::Cached(cached)).await;
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        glib::g_log!(
+                            glib::LogLevel::Warning,
+                            "Error reading cached events : {}",
+                            err
+                        );
+                    }
+                }
+            }
+            let fetched = api.fetch_events(&search, page, pagination.limit).await;
+            if let (Some(cache), Ok((events, _))) = (&cache, &fetched) {
+                let events = events
+                    .iter()
+                    .filter_map(|event| event.as_ref().ok())
+                    .cloned()
+                    .collect::<Vec<Event>>();
+                if let Err(err) = cache.save_timeline(&events).await {
+                    glib::g_log!(glib::LogLevel::Warning, "Error caching events : {}", err);
+                }
+            }
+            let _ = sender.send(Loaded::Fetched(fetched)).await;
+        });
+        window.page_request.replace(Some(request.abort_handle()));
+        glib::spawn_future_local(glib::clone!(
+            #[strong]
+            obj,
+            async move {
+                while let Ok(loaded) = receiver.recv().await {
+                    let window = obj.imp();
+                    if window.timeline_generation.get() != generation {
+                        return;
+                    }
+                    match loaded {
+                        Loaded::Cached(cached) => show_cached_events(&obj, cached),
+                        Loaded::Fetched(fetched) => {
+                            window.loading_page.set(false);
+                            window.page_spinner.set_visible(false);
+                            window.page_request.take();
+                            match fetched {
+                                Ok((events_with_error, total)) => {
+                                    show_page(&obj, events_with_error, total);
+                                }
+                                Err(err) => {
+                                    glib::g_log!(
+                                        glib::LogLevel::Warning,
+                                        "Error fetching events : {}",
+                                        err
+                                    );
+                                    match (err, window.cached_since.get()) {
+                                        // What arrived is shown, with a word about the rest
+                                        (
+                                            EventsFetchError::GraphQLErrors(GraphQLErrors {
+                                                partial_data: Some((events_with_error, total)),
+                                                ..
+                                            }),
+                                            _,
+                                        ) => {
+                                            show_page(&obj, events_with_error, total);
+                                            show_partial_page_banner(&obj);
+                                        }
+                                        (_, Some(updated_at)) => {
+                                            show_cache_banner(&obj, updated_at, true)
+                                        }
+                                        (err, None) => {
+                                            window
+                                                .timeline_error
+                                                .set_description(Some(&err.to_string()));
+                                            window.timeline_stack.set_visible_child_name("error");
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        ));
+    }
+
+    /// Adds a page of events to the timeline, leaving out the ones that could
+    /// not be decoded.
+    fn show_page(
+        obj: &super::MobilisationWindow,
+        events_with_error: Vec<Result<Event, EventDecodeError>>,
+        total: i64,
+    ) {
+        let window = obj.imp();
+        let mut pagination = window.pagination.get();
+        pagination.page_loaded(events_with_error.len(), total);
+        window.pagination.set(pagination);
+        let models = events_with_error
+            .into_iter()
+            .filter_map(|event| match event {
+                Ok(event) => Some(event),
+                Err(err) => {
+                    glib::g_log!(glib::LogLevel::Warning, "Error decoding event : {}", err);
+                    None
+                }
+            })
+            .map(|event| MobilisationEventPreviewModel::new(&event))
+            .collect::<Vec<MobilisationEventPreviewModel>>();
+        if let Some(store) = window.event_store.get() {
+            // Fresh events take the place of the cached ones
+            if window.cached_since.take().is_some() {
+                store.remove_all();
+                window.cache_banner.set_revealed(false);
+            }
+            store.extend_from_slice(&models);
+        }
+        refresh_timeline_sections(obj);
+        update_timeline_state(obj);
+        load_more_if_needed(obj);
+    }
+
+    /// Tells that the server only sent part of the events, which stays true
+    /// of the timeline until it is loaded again.
+    fn show_partial_page_banner(obj: &super::MobilisationWindow) {
+        let banner = &obj.imp().cache_banner;
+        banner.set_title(&gettext("Some events could not be loaded."));
+        banner.set_button_label(None);
+        banner.set_revealed(true);
+    }
+
+    /// Shows the events saved the last time while the instance is reached.
+    fn show_cached_events(obj: &super::MobilisationWindow, cached: Cached<Vec<Event>>) {
+        let window = obj.imp();
+        let models = cached
+            .value
+            .iter()
+            .map(MobilisationEventPreviewModel::new)
+            .collect::<Vec<MobilisationEventPreviewModel>>();
+        if let Some(store) = window.event_store.get() {
+            store.extend_from_slice(&models);
+        }
+        window.cached_since.set(Some(cached.updated_at));
+        refresh_timeline_sections(obj);
+        show_cache_banner(obj, cached.updated_at, false);
+        update_timeline_state(obj);
+    }
+
+    /// Tells how old the events shown are, with a way to try again once the
+    /// instance could not be reached.
+    fn show_cache_banner(
+        obj: &super::MobilisationWindow,
+        updated_at: chrono::DateTime<chrono::Utc>,
+        failed: bool,
+    ) {
+        let banner = &obj.imp().cache_banner;
+        let updated = last_updated_label(updated_at, chrono::Utc::now());
+        if failed {
+            banner.set_title(
+                &gettext("Could not reach the instance. {updated}").replace("{updated}", &updated),
+            );
+            banner.set_button_label(Some(&gettext("_Retry")));
+        } else {
+            banner.set_title(&updated);
+            banner.set_button_label(None);
+        }
+        banner.set_revealed(true);
+    }
+
+    fn setup_search(obj: &super::MobilisationWindow) {
+        let window = obj.imp();
+        window.search_bar.set_key_capture_widget(Some(obj));
+        window.search_bar.connect_entry(&*window.search_entry);
+        // The entry already waits for typing to pause before emitting this.
+        window.search_entry.connect_search_changed(glib::clone!(
+            #[weak]
+            obj,
+            move |entry| {
+                let term = entry.text().trim().to_string();
+                if obj.imp().search.borrow().term == term {
+                    return;
+                }
+                obj.imp().search.borrow_mut().term = term;
+                reset_event_timeline(&obj);
+            }
+        ));
+        window
+            .search_bar
+            .connect_search_mode_enabled_notify(glib::clone!(
+                #[weak]
+                obj,
+                move |search_bar| {
+                    if !search_bar.is_search_mode() {
+                        obj.imp().search_entry.set_text("");
+                    }
+                }
+            ));
+    }
+
+    /// Mirrors the filters turned on in the sidebar into the search and the
+    /// chips above the timeline. Returns whether the search changed.
+    fn sync_filters(obj: &super::MobilisationWindow) -> bool {
+        let window = obj.imp();
+        let rows = window.sidebar.active_rows();
+        window.filter_chips.remove_all();
+        rows.iter().for_each(|row| {
+            window
+                .filter_chips
+                .append(&MobilisationFilterChip::new(row))
+        });
+        window.filter_chips.set_visible(!rows.is_empty());
+        let categories = window.sidebar.selected_categories();
+        let languages = window.sidebar.selected_languages();
+        let statuses = window.sidebar.selected_statuses();
+        let mut search = window.search.borrow_mut();
+        let changed = search.categories != categories
+            || search.languages != languages
+            || search.statuses != statuses;
+        search.categories = categories;
+        search.languages = languages;
+        search.statuses = statuses;
+        changed
+    }
+
+    fn setup_filters(obj: &super::MobilisationWindow) {
+        obj.imp().sidebar.connect_filters_changed(glib::clone!(
+            #[weak]
+            obj,
+            move |_| {
+                if sync_filters(&obj) {
+                    reset_event_timeline(&obj);
+                }
+            }
+        ));
+    }
+
+    fn cache_directory() -> std::path::PathBuf {
+        glib::user_cache_dir().join("mobilisation")
+    }
+
+    /// Keeps the session of the user in the keyring whenever the client
+    /// renews it, so that they stay logged in across restarts.
+    fn make_api(
+        obj: &super::MobilisationWindow,
+        instance_url: &Url,
+        settings: &gio::Settings,
+    ) -> Arc<dyn MobilizonApi> {
+        let keyring = SessionKeyring::new(instance_url);
+        let (sender, receiver) = async_channel::unbounded::<SessionChange>();
+        let listener_sender = sender.clone();
+        let mut client = MobilizonClient::new(instance_url)
+            .with_picture_directory(cache_directory().join("pictures"))
+            .with_session_listener(Box::new(move |change| {
+                let _ = listener_sender.try_send(change);
+            }));
+        if !settings.string("account-email").is_empty() {
+            let keyring = keyring.clone();
+            client = client.with_saved_session(Box::pin(async move {
+                keyring
+                    .load()
+                    .await
+                    .map_err(|err| {
+                        glib::g_log!(glib::LogLevel::Warning, "Error reading session : {}", err);
+                    })
+                    .ok()
+                    .flatten()
+            }));
+        } else {
+            // Leftovers of a session on another instance
+            let _ = sender.try_send(SessionChange::Ended);
+        }
+        // Saved in order on the runtime, oo7 needs it
+        let (ui_sender, ui_receiver) = async_channel::unbounded::<SessionChange>();
+        runtime().spawn(async move {
+            while let Ok(change) = receiver.recv().await {
+                let saved = match &change {
+                    SessionChange::Renewed(refresh_token) => keyring.save(refresh_token).await,
+                    SessionChange::Ended | SessionChange::Expired => keyring.clear().await,
+                };
+                if let Err(err) = saved {
+                    glib::g_log!(glib::LogLevel::Warning, "Error saving session : {}", err);
+                }
+                let _ = ui_sender.send(change).await;
+            }
+        });
+        let window = obj.downgrade();
+        glib::spawn_future_local(glib::clone!(
+            #[strong]
+            settings,
+            async move {
+                while let Ok(change) = ui_receiver.recv().await {
+                    let Some(obj) = window.upgrade() else {
+                        break;
+                    };
+                    match change {
+                        SessionChange::Renewed(_) => {}
+                        SessionChange::Ended => settings.reset("account-email"),
+                        SessionChange::Expired => {
+                            settings.reset("account-email");
+                            show_session_expired(&obj);
+                        }
+                    }
+                }
+            }
+        ));
+        Arc::new(client)
+    }
+
+    /// Offers to log back in once the server refused the session.
+    fn show_session_expired(obj: &super::MobilisationWindow) {
+        let alert = adw::AlertDialog::new(
+            Some(&gettext("Session Expired")),
+            Some(&gettext(
+                "Log in again to see your events and take part in them.",
+            )),
+        );
+        alert.add_responses(&[
+            ("close", &gettext("_Close")),
+            ("login", &gettext("_Log In")),
+        ]);
+        alert.set_response_appearance("login", adw::ResponseAppearance::Suggested);
+        alert.set_default_response(Some("login"));
+        alert.connect_response(
+            Some("login"),
+            glib::clone!(
+                #[weak]
+                obj,
+                move |_, _| show_login(&obj)
+            ),
+        );
+        alert.present(Some(obj));
+    }
+
+    fn update_account_actions(obj: &super::MobilisationWindow) {
+        let imp = obj.imp();
+        let logged_in = imp
+            .settings
+            .get()
+            .is_some_and(|settings| !settings.string("account-email").is_empty());
+        let has_instance = imp.api.borrow().is_some();
+        obj.action_set_enabled("win.login", has_instance && !logged_in);
+        obj.action_set_enabled("win.logout", has_instance && logged_in);
+        imp.sidebar.set_my_events_visible(has_instance && logged_in);
+    }
+
+    /// Exports every event of the timeline, filters included, fetching the
+    /// pages not loaded yet.
+    fn export_timeline(obj: &super::MobilisationWindow) {
+        let imp = obj.imp();
+        let Some(api) = imp.api.borrow().clone() else {
+            return;
+        };
+        let instance_url = instance_url(obj);
+        let search = imp.search.borrow().clone();
         let (sender, receiver) = async_channel::unbounded();
         runtime().spawn(async move {
             let _ = sender
-                .send(fetch_events(http_client(), "https://mobilizon.fr/api").await)
+                .send(fetch_all_events(api.as_ref(), &search).await)
                 .await;
         });
         glib::spawn_future_local(glib::clone!(
-            #[strong]
+            #[weak]
             obj,
             async move {
-                receiver
+                let _ = receiver
                     .recv()
                     .await
                     .map(|value| match value {
-                        Ok((events_with_error, _)) => {
-                            let events = events_with_error
-                                .into_iter()
-                                .filter_map(|event| match event {
-                                    Ok(event) => Some(event),
-                                    Err(err) => {
-                                        glib::g_log!(
-                                            glib::LogLevel::Warning,
-                                            "Error decoding event : {}",
-                                            err
-                                        );
-                                        None
-                                    }
+                        Ok(events) => {
+                            let events = events
+                                .iter()
+                                .map(|event| CalendarEvent {
+                                    // Where Mobilizon shows an event
+                                    url: instance_url.as_ref().and_then(|url| {
+                                        url.join(&format!("events/{}", event.id)).ok()
+                                    }),
+                                    ..CalendarEvent::from(event)
                                 })
-                                .collect();
-                            create_event_timeline(&obj.imp(), &events);
+                                .collect::<Vec<CalendarEvent>>();
+                            export_calendar(&obj, &gettext("Events"), events);
                         }
                         Err(err) => {
-                            glib::g_log!(glib::LogLevel::Warning, "Error fetching events : {}", err);
+                            glib::g_log!(
+                                glib::LogLevel::Warning,
+                                "Error fetching events to export : {}",
+                                err
+                            );
+                            show_export_error(Some(&obj), &err.to_string());
                         }
                     })
                     .map_err(|err| {
                         glib::g_log!(glib::LogLevel::Error, "Channel error : {}", err);
-                        obj.imp().sidebar.show_error();
-                    })
+                    });
             }
         ));
     }
 
+    fn show_login(obj: &super::MobilisationWindow) {
+        if let Some(api) = obj.imp().api.borrow().clone() {
+            MobilisationLoginDialog::new(api).present(Some(obj));
+        }
+    }
+
+    fn logout(obj: &super::MobilisationWindow) {
+        let Some(api) = obj.imp().api.borrow().clone() else {
+            return;
+        };
+        let (sender, receiver) = async_channel::unbounded();
+        runtime().spawn(async move {
+            let _ = sender.send(api.logout().await).await;
+        });
+        glib::spawn_future_local(async move {
+            let _ = receiver
+                .recv()
+                .await
+                .map(|value| {
+                    // The session is forgotten locally in any case
+                    if let Err(err) = value {
+                        glib::g_log!(glib::LogLevel::Warning, "Error logging out : {}", err);
+                    }
+                })
+                .map_err(|err| {
+                    glib::g_log!(glib::LogLevel::Error, "Channel error : {}", err);
+                });
+        });
+    }
+
+    /// Address of the instance picked, if one has been.
+    fn instance_url(obj: &super::MobilisationWindow) -> Option<Url> {
+        obj.imp()
+            .settings
+            .get()
+            .and_then(|settings| Url::parse(settings.string("instance-url").as_str()).ok())
+    }
+
+    /// Shows the onboarding when no instance has been picked yet, otherwise
+    /// (re)loads everything from the configured instance.
+    pub(super) fn load_instance(obj: &super::MobilisationWindow) {
+        let imp = obj.imp();
+        match instance_url(obj) {
+            None => imp.main_stack.set_visible_child_name("onboarding"),
+            Some(instance_url) => {
+                imp.main_stack.set_visible_child_name("timeline");
+                imp.navigation_view.pop_to_tag("timeline");
+                imp.sidebar.show_loading();
+                imp.categories.replace(Vec::new());
+                sync_filters(obj);
+                let Some(settings) = imp.settings.get() else {
+                    return;
+                };
+                imp.instance_generation
+                    .set(imp.instance_generation.get() + 1);
+                let api = make_api(obj, &instance_url, settings);
+                let cache = Cache::new(&cache_directory(), &instance_url);
+                load_config(obj, api.clone(), cache.clone());
+                imp.api.replace(Some(api));
+                imp.cache.replace(Some(cache));
+                reset_event_timeline(obj);
+            }
+        }
+        update_account_actions(obj);
+    }
+
     impl ObjectImpl for MobilisationWindow {
         fn constructed(&self) {
             let obj = self.obj();
             self.parent_constructed();
-            load_config(obj.imp().sidebar.as_ref());
-            load_events(obj.as_ref());
+            let settings = settings();
+            settings.connect_changed(
+                Some("instance-url"),
+                glib::clone!(
+                    #[weak]
+                    obj,
+                    move |settings, _| {
+                        // Sessions only make sense on the instance they were opened on
+                        settings.reset("account-email");
+                        load_instance(&obj)
+                    }
+                ),
+            );
+            settings.connect_changed(
+                Some("account-email"),
+                glib::clone!(
+                    #[weak]
+                    obj,
+                    move |_, _| update_account_actions(&obj)
+                ),
+            );
+            let _ = self.settings.set(settings);
+            self.search.borrow_mut().boost_languages =
+                language_codes_from_locales(&glib::language_names());
+            setup_event_timeline(obj.as_ref());
+            setup_my_events(obj.as_ref());
+            setup_search(obj.as_ref());
+            setup_filters(obj.as_ref());
+            load_instance(obj.as_ref());
         }
     }
     impl WidgetImpl for MobilisationWindow {}